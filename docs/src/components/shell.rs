@@ -5,7 +5,7 @@ use alloc::{
     vec::Vec,
 };
 use momenta::prelude::*;
-use momenta_router::RouterContext;
+use momenta_router::use_router;
 use wasm_bindgen::JsCast;
 
 pub static GITHUB_LINK: &str = "https://github.com/elcharitas/momenta";
@@ -373,13 +373,11 @@ pub fn Header(props: &HeaderProps) -> Node {
     }
 }
 
-pub struct NavigationProps {
-    pub router: RouterContext,
-}
-
 #[component]
-pub fn Navigation(props: &NavigationProps) -> Node {
-    let current_path = props.router.current_path();
+pub fn Navigation() -> Node {
+    let current_path = use_router()
+        .expect("Navigation must be rendered inside a provided RouterContext")
+        .current_path();
 
     let nav_link = move |path: &'static str, label: &'static str| {
         let is_active = current_path.get() == path;
@@ -13,7 +13,7 @@ use pages::*;
 
 #[component]
 fn App() -> Node {
-    let router = RouterContext::with_base(RouterMode::Pathname, docs_base_path());
+    let router = RouterContext::with_base(RouterMode::Pathname, docs_base_path()).provide();
     let current_path = router.current_path();
     let theme = create_signal("dark");
     let mobile_menu_open = create_signal(false);
@@ -52,7 +52,7 @@ fn App() -> Node {
                 {when!(current_path.get() != "/" =>
                     <aside class="hidden lg:block w-64 shrink-0 border-r border-border/50">
                         <div class="sticky top-14 h-[calc(100vh-3.5rem)] overflow-y-auto py-6 px-1">
-                            <Navigation {router} />
+                            <Navigation />
                         </div>
                     </aside>
                 )}
@@ -68,7 +68,7 @@ fn App() -> Node {
                                 </button>
                             </div>
                             <div class="overflow-y-auto p-4">
-                                <Navigation {router} />
+                                <Navigation />
                             </div>
                         </div>
                     </div>
@@ -82,7 +82,7 @@ fn App() -> Node {
                     )}
 
                     {routes!(router, current_path, {
-                        "/" => |_| rsx!{ <HomePage {router} /> },
+                        "/" => |_| rsx!{ <HomePage /> },
                         "/getting-started" => |_| rsx!{ <GettingStartedPage /> },
                         "/philosophy" => |_| rsx!{ <PhilosophyPage /> },
                         "/rsx" => |_| rsx!{ <RsxPage /> },
@@ -350,6 +350,39 @@ create_effect_with_cleanup(move || {
                     </p>
                 </Note>
 
+                <h3>Effect Phases</h3>
+                <p>"Effects created inside a component run in three phases around the DOM patch. create_render_effect runs right after the component function, before its changes reach the DOM. create_layout_effect runs once the whole render has been patched in, before the next frame, which makes it the place to measure elements or move focus. create_effect runs last, after every layout effect. Within each phase, child components run before their parents:"</p>
+                <CodeBlock
+                    language="rust"
+                    filename="src/main.rs"
+                    highlight=""
+                    code={r#"use momenta::prelude::*;
+
+#[component]
+fn Search() -> Node {
+    let open = create_signal(false);
+
+    create_render_effect(move || {
+        log!("Rendering, open = {}", open.get());
+    });
+
+    // The input below is already in the DOM here
+    create_layout_effect(move || {
+        if open.get() {
+            focus_element("search-input");
+        }
+    });
+
+    create_effect(move || {
+        log!("Search is now {}", if open.get() { "open" } else { "closed" });
+    });
+
+    rsx! {
+        <input id="search-input" on:focus={move |_| open.set(true)} />
+    }
+}"#}
+                />
+
                 <h3>Batch Updates</h3>
                 <p>"When updating multiple signals at once, wrap them in batch() to trigger only a single re-render:"</p>
                 <CodeBlock
@@ -381,6 +414,22 @@ batch(|| {
                     </p>
                 </Note>
 
+                <h3>Transactions</h3>
+                <p>"A transaction is a batch that can be rolled back. If the closure returns an Err, every signal it changed is restored, which makes optimistic updates easy to undo. In-place updates that would need a copy of the value to undo, such as update or insert on a map signal, keep their new value and are reported to the error hook; use update_if_changed inside transactions instead:"</p>
+                <CodeBlock
+                    language="rust"
+                    filename="src/main.rs"
+                    highlight=""
+                    code={r#"use momenta::prelude::*;
+
+let likes = create_signal(10);
+
+let result = transaction(|| {
+    likes.set(11);     // optimistic update
+    api::like_post()   // Err(..) puts likes back to 10
+});"#}
+                />
+
                 <h3>Effect Ordering</h3>
                 <p>"Effects are executed in the order they are created"</p>
                 <CodeBlock
@@ -700,16 +749,36 @@ rsx! {
     </ul>
 }"#}
                 />
+                <h3>Keyed Lists</h3>
+                <p>"map rebuilds every row whenever the vector changes. For long lists, map_keyed renders each item in its own scope and keeps it for as long as its key is in the list, so pushing one row renders one row and reordering just moves the existing elements:"</p>
+                <CodeBlock
+                    language="rust"
+                    filename="src/main.rs"
+                    highlight=""
+                    code={r#"#[derive(Clone, PartialEq)]
+struct Todo {
+    id: u32,
+    title: String,
+}
+
+let todos = create_signal(vec![Todo { id: 1, title: "Write docs".to_string() }]);
+
+rsx! {
+    <ul>
+        {map_keyed(todos, |todo| todo.id, |todo| rsx! { <li>{&todo.title}</li> })}
+    </ul>
+}"#}
+                />
 
                 <h2 id="signal-value">Custom Types with SignalValue</h2>
-                <p>"To store custom types in signals, derive the SignalValue trait:"</p>
+                <p>"Any type that is 'static and Send can be stored in a signal, with no derive or impl needed:"</p>
                 <CodeBlock
                     language="rust"
                     filename="src/main.rs"
                     highlight=""
                     code={r#"use momenta::prelude::*;
 
-#[derive(Clone, PartialEq, SignalValue)]
+#[derive(Clone, PartialEq)]
 struct User {
     name: String,
     age: u32,
@@ -731,12 +800,16 @@ user.set(User {
                 />
                 <Note variant="info">
                     <p>
-                        <strong>"Built-in support:"</strong>
-                        " SignalValue is already implemented for all numeric types, bool, char, String, &'static str, "
-                        <code>"Vec<T>"</code>
-                        ", and "
-                        <code>"Option<T>"</code>
-                        "."
+                        <strong>"Blanket implementation:"</strong>
+                        " SignalValue is implemented for every "
+                        <code>"T: Any + Send"</code>
+                        ", including tuples, arrays, "
+                        <code>"Box<T>"</code>
+                        " and "
+                        <code>"Arc<T>"</code>
+                        ". Types holding an "
+                        <code>"Rc"</code>
+                        " or borrowed data are rejected at compile time. The derive still compiles but is no longer needed."
                     </p>
                 </Note>
 
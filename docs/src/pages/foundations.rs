@@ -120,7 +120,7 @@ assert_eq!(tripled.get(), 30);"#}
 let count = create_signal(5);
 
 // Create a memoized computation
-let expensive = create_memo("double_count", move || {
+let expensive = create_memo(move || {
     // This only runs when dependencies change
     println!("Computing...");
     count.get() * 2
@@ -139,6 +139,10 @@ count.set(10); // Next get() will recompute"#}
                 <Note variant="info">
                     <p>
                         <strong>Note:</strong> Memoization requires the memoization or full-reactivity feature flag.
+                        The cached value lives with the component that created the memo, survives its re-renders and
+                        is dropped when the component is disposed. To keep values across renders of list rows
+                        that are re-created each time, look them up in a cache from create_memo_cache, which can
+                        be given a capacity to keep only the most recently used values.
                     </p>
                 </Note>
 
@@ -162,7 +166,6 @@ count.set(10); // Next get() will recompute"#}
                     <li>Use create_computed for simple derived values</li>
                     <li>Use create_memo for expensive computations</li>
                     <li>Keep computation functions pure (no side effects)</li>
-                    <li>Name your memos descriptively for easier debugging</li>
                     <li>Avoid circular dependencies between computed signals</li>
                 </ul>
             </section>
@@ -727,6 +730,9 @@ fn UserProfile() -> Node {
                 ResourceStatus::Resolved => rsx! {
                     <h1>"User: " {user_resource.get().unwrap_or_default()}</h1>
                 },
+                ResourceStatus::Errored(_) => rsx! {
+                    <div class="error">"Could not load user data"</div>
+                },
             }}
         </div>
     }
@@ -751,31 +757,36 @@ async fn fetch_user_data() -> String {
                     language="rust"
                     filename="src/main.rs"
                     highlight=""
-                    code={r#"use momenta::signals::create_resource;
+                    code={r#"use momenta::signals::{create_resource, create_resource_with_source};
 
 let user_data = create_resource(|| async {
     fetch_user().await
 });
 
-let user_posts = create_resource(move || {
-    let user_id = user_id.get();
-    async move {
-        fetch_user_posts(user_id).await
-    }
+// Refetched whenever user_id changes. A response for an older id that
+// arrives late is discarded.
+let user_posts = create_resource_with_source(user_id, |user_id| async move {
+    fetch_user_posts(user_id).await
 });
 
-let search_results = create_resource(move || {
-    let query = search_query.get();
-    async move {
-        if query.is_empty() {
-            Vec::new()
-        } else {
-            search_api(query).await
-        }
+let search_results = create_resource_with_source(search_query, |query| async move {
+    if query.is_empty() {
+        Ok(Vec::new())
+    } else {
+        search_api(query).await
     }
 });"#}
                 />
 
+                <Note variant="info">
+                    <p>
+                        <strong>"Errors:"</strong>
+                        " Fetchers passed to create_resource_with_source return a Result. An Err moves the resource into ResourceStatus::Errored and is available from "
+                        <code>"resource.error()"</code>
+                        "."
+                    </p>
+                </Note>
+
                 <h2 id="resource-states" class="font-bold tracking-tight">Resource States</h2>
                 <CodeBlock
                     language="rust"
@@ -908,6 +919,9 @@ fn ResourceWithEffects() -> Node {
             ResourceStatus::Idle | ResourceStatus::Pending | ResourceStatus::Loading => {
                 log!("Waiting for resource to resolve");
             }
+            ResourceStatus::Errored(_) => {
+                log!("Resource failed to load");
+            }
         }
     });
 
@@ -5,7 +5,7 @@ use alloc::{format, vec};
 use momenta::prelude::*;
 
 #[component]
-pub fn HomePage(_props: &NavigationProps) -> Node {
+pub fn HomePage() -> Node {
     rsx! {
         <div class="fade-in">
             <div class="px-6 pt-20 pb-16 sm:px-8 text-center">
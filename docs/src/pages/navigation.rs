@@ -211,7 +211,7 @@ impl RouteMatch {
 
                 <h2 id="best-practices">Best Practices</h2>
                 <ul>
-                    <li>"Create the RouterContext once at the top level and pass it down via props"</li>
+                    <li>"Create the RouterContext once at the top level, call provide() on it and read it back with use_router() in nested components"</li>
                     <li>"Use Hash mode for simple static deployments"</li>
                     <li>"Use Pathname mode when you have server-side URL rewriting"</li>
                     <li>"Keep route patterns simple and readable"</li>
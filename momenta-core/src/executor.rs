@@ -0,0 +1,174 @@
+//! Ready-made spawners for running async work such as resource fetches
+//! outside the browser.
+//!
+//! Each module installs itself with [`set_spawner`](crate::signals::set_spawner);
+//! pick the one matching the executor your server or test already uses.
+
+/// A single-threaded `futures-executor` pool per thread, handy for unit tests
+/// and simple servers.
+///
+/// # Example
+/// ```ignore
+/// use momenta_core::executor::local;
+///
+/// local::install();
+/// // ... render something that creates resources ...
+/// local::run_until_stalled();
+/// ```
+#[cfg(feature = "futures-executor")]
+pub mod local {
+    use crate::signals::set_spawner;
+    use core::{cell::RefCell, future::Future};
+    use futures_executor::{LocalPool, LocalSpawner};
+    use futures_task::LocalSpawn;
+
+    std::thread_local! {
+        static POOL: RefCell<LocalPool> = RefCell::new(LocalPool::new());
+        static SPAWNER: LocalSpawner = POOL.with(|pool| pool.borrow().spawner());
+    }
+
+    /// Spawn futures onto the pool of the thread that spawns them.
+    pub fn install() {
+        set_spawner(|future| {
+            SPAWNER.with(|spawner| {
+                spawner
+                    .spawn_local_obj(future.into())
+                    .expect("the local executor has shut down")
+            })
+        });
+    }
+
+    /// Run this thread's tasks until none of them can make further progress.
+    ///
+    /// Must not be called from inside a task running on the same pool.
+    pub fn run_until_stalled() {
+        POOL.with(|pool| pool.borrow_mut().run_until_stalled());
+    }
+
+    /// Drive `future` to completion, running this thread's tasks alongside it.
+    pub fn block_on<F: Future>(future: F) -> F::Output {
+        POOL.with(|pool| pool.borrow_mut().run_until(future))
+    }
+}
+
+/// Spawns futures with `tokio::task::spawn_local`.
+///
+/// Reactive futures are not `Send`, so they have to be spawned from inside a
+/// [`tokio::task::LocalSet`]; spawning outside of one panics.
+///
+/// # Example
+/// ```ignore
+/// use momenta_core::executor;
+///
+/// executor::tokio::install();
+/// tokio::task::LocalSet::new()
+///     .run_until(async {
+///         // ... render something that creates resources ...
+///     })
+///     .await;
+/// ```
+#[cfg(feature = "tokio")]
+pub mod tokio {
+    use crate::signals::set_spawner;
+
+    pub fn install() {
+        set_spawner(|future| {
+            ::tokio::task::spawn_local(future);
+        });
+    }
+}
+
+#[cfg(all(test, any(feature = "futures-executor", feature = "tokio")))]
+mod tests {
+    use crate::signals::{
+        Resource, ResourceStatus, Runtime, SPAWNER, TEST_MUTEX, create_resource, in_root_scope,
+    };
+    use core::{
+        future::Future,
+        pin::Pin,
+        task::{Context, Poll},
+    };
+
+    /// Pending once, then woken through the executor's real waker.
+    struct YieldOnce(bool);
+
+    impl Future for YieldOnce {
+        type Output = ();
+
+        fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<()> {
+            if self.0 {
+                return Poll::Ready(());
+            }
+            self.0 = true;
+            cx.waker().wake_by_ref();
+            Poll::Pending
+        }
+    }
+
+    /// Render a component with a resource inside `runtime` and return it.
+    fn render_resource(runtime: &Runtime) -> Resource<u32> {
+        let (answer, _) = runtime.enter(|| {
+            in_root_scope(|| {
+                create_resource(|| async {
+                    YieldOnce(false).await;
+                    42
+                })
+            })
+        });
+        answer
+    }
+
+    #[cfg(feature = "futures-executor")]
+    #[test]
+    fn local_executor_resolves_resources() {
+        let _guard = TEST_MUTEX.lock();
+        super::local::install();
+
+        let runtime = Runtime::new();
+        let answer = render_resource(&runtime);
+        assert_eq!(
+            runtime.enter(|| answer.status().get()),
+            ResourceStatus::Loading
+        );
+
+        super::local::run_until_stalled();
+        runtime.enter(|| {
+            assert_eq!(answer.status().get(), ResourceStatus::Resolved);
+            assert_eq!(answer.get(), Some(42));
+        });
+
+        *SPAWNER.lock() = None;
+    }
+
+    #[cfg(feature = "tokio")]
+    #[test]
+    fn tokio_executor_resolves_resources() {
+        let _guard = TEST_MUTEX.lock();
+        super::tokio::install();
+
+        let tokio_runtime = ::tokio::runtime::Builder::new_current_thread()
+            .build()
+            .unwrap();
+        let runtime = Runtime::new();
+        ::tokio::task::LocalSet::new().block_on(&tokio_runtime, async {
+            let answer = render_resource(&runtime);
+            assert_eq!(
+                runtime.enter(|| answer.status().get()),
+                ResourceStatus::Loading
+            );
+
+            for _ in 0..10 {
+                if runtime.enter(|| answer.status().get()) == ResourceStatus::Resolved {
+                    break;
+                }
+                ::tokio::task::yield_now().await;
+            }
+            runtime.enter(|| {
+                assert_eq!(answer.status().get(), ResourceStatus::Resolved);
+                assert_eq!(answer.get(), Some(42));
+            });
+        });
+
+        *SPAWNER.lock() = None;
+    }
+}
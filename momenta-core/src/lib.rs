@@ -9,7 +9,11 @@
 //! This is typically not used directly; use the `momenta` crate instead.
 
 extern crate alloc;
+#[cfg(feature = "std")]
+extern crate std;
 
+#[cfg(any(feature = "futures-executor", feature = "tokio"))]
+pub mod executor;
 pub mod nodes;
 pub mod signals;
 
@@ -20,9 +24,11 @@ pub mod prelude {
     #[cfg(any(feature = "computed", feature = "full-reactivity"))]
     pub use crate::signals::create_computed;
     #[cfg(any(feature = "memoization", feature = "full-reactivity"))]
-    pub use crate::signals::create_memo;
+    pub use crate::signals::{create_memo, create_memo_cache};
     pub use crate::signals::{
-        Signal, SignalValue, batch, create_effect, create_effect_with_cleanup, create_signal,
+        Lens, Signal, SignalValue, Store, batch, create_async_effect, create_effect,
+        create_effect_with_cleanup, create_layout_effect, create_render_effect, create_signal,
+        create_store, map_keyed, provide_context, transaction, untrack, use_context, watch,
     };
     pub use momenta_macros::{component, rsx, when};
 }
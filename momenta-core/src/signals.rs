@@ -1,23 +1,94 @@
 use crate::nodes::Node;
+#[cfg(feature = "serde")]
+use alloc::string::String;
 use alloc::{
     boxed::Box,
-    collections::BTreeMap,
-    string::{String, ToString},
+    collections::{BTreeMap, BTreeSet},
+    string::ToString,
     sync::Arc,
     vec::Vec,
 };
 use core::{
-    any::Any,
+    any::{Any, TypeId},
     cmp::Ordering,
+    future::Future,
     marker::PhantomData,
     ops::{AddAssign, DivAssign, MulAssign, Not, SubAssign},
-    sync::atomic::{AtomicBool, Ordering as AtomicOrdering},
+    panic::Location,
+    pin::Pin,
+    sync::atomic::{AtomicBool, AtomicUsize, Ordering as AtomicOrdering},
+    task::{Context, Poll, Waker},
 };
 use spin::Mutex;
+#[cfg(feature = "std")]
+use std::collections::{HashMap, HashSet};
+
+#[cfg(any(feature = "devtools", test))]
+mod devtools;
+mod history;
+mod keyed_list;
+mod selectors;
+mod stores;
+#[cfg(feature = "futures")]
+mod streams;
+mod timers;
+
+#[cfg(any(feature = "devtools", test))]
+pub use devtools::{
+    GraphEdge, GraphNode, GraphSnapshot, ScopeSnapshot, SignalSnapshot, graph_snapshot,
+};
+pub use history::{History, create_history, create_history_with_coalescing};
+pub use keyed_list::{
+    LIST_MARKER_PREFIX, ListPatcher, has_list_patcher, map_keyed, set_list_patcher,
+};
+pub use selectors::{Selector, create_selector};
+pub use stores::{Lens, Store, create_store};
+#[cfg(feature = "futures")]
+pub use streams::{SignalStream, create_signal_from_stream};
+pub use timers::{
+    Timer, TimerId, VirtualClock, create_interval, debounced, has_timer, set_timer, throttled,
+};
 
 type ScopeCallback = Arc<dyn Fn(&Node) + Send + Sync>;
 type ScopeEffect = Box<dyn Fn() + Send>;
 type ScopeEffectCleanup = Box<dyn FnOnce() + Send>;
+type ComputeFn = Box<dyn Fn() -> Box<dyn Any + Send> + Send>;
+type ValueEq = fn(&dyn Any, &dyn Any) -> bool;
+/// Decides whether a new value of a signal differs from the current one.
+type SignalEq = Box<dyn Fn(&dyn Any, &dyn Any) -> bool + Send>;
+type LocalFuture = Pin<Box<dyn Future<Output = ()>>>;
+type Spawner = Arc<dyn Fn(LocalFuture) + Send + Sync>;
+type ErrorHook = Arc<dyn Fn(&ReactiveError) + Send + Sync>;
+/// Identifies one part of a signal's value, e.g. the path to a store field.
+type DependencyKey = Vec<u64>;
+/// Turns the value of a selector's source into the key scopes subscribe to.
+type SelectionKeyFn = fn(&dyn Any) -> Option<u64>;
+/// Receives every new value of a signal, adding the waker of a stream that
+/// waits for it; returns false to unsubscribe.
+#[cfg(feature = "futures")]
+type SignalListener = Box<dyn FnMut(&dyn Any, &mut Vec<Waker>) -> bool + Send>;
+/// Turns the value of a serialized signal into JSON.
+#[cfg(feature = "serde")]
+type SerializeFn = fn(&dyn Any) -> Option<serde_json::Value>;
+
+/// Upper bound on flush rounds before pending work is dropped.
+const MAX_FLUSH_ITERATIONS: usize = 100;
+
+/// Most scope renders a single flush may perform, see [`set_max_render_iterations`].
+static MAX_RENDER_ITERATIONS: AtomicUsize = AtomicUsize::new(100);
+
+static ERROR_HOOK: Mutex<Option<ErrorHook>> = Mutex::new(None);
+
+/// When an effect runs relative to the scope's render and DOM patch.
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+enum EffectPhase {
+    /// Right after the scope function, before the scope callback patches the DOM.
+    Render,
+    /// After the outermost render has patched the DOM.
+    Layout,
+    /// After every layout effect of the same flush.
+    Passive,
+}
 
 /// Per-scope data stored in a Vec for O(1) access.
 struct ScopeData {
@@ -27,8 +98,26 @@ struct ScopeData {
     effects: Vec<Option<ScopeEffect>>,
     effect_cleanups: Vec<Option<ScopeEffectCleanup>>,
     effect_executing: Vec<bool>,
+    /// When each effect in `effects` runs relative to the DOM patch.
+    effect_phases: Vec<EffectPhase>,
     function: Option<Box<dyn FnMut() -> Node + Send>>,
     callback: Option<ScopeCallback>,
+    /// The scope that was current when this scope was created.
+    parent: Option<usize>,
+    /// Scopes created while this scope was rendering. They are disposed
+    /// before this scope re-renders or is itself disposed.
+    children: Vec<usize>,
+    /// Set for keyed lists, which dispose the children they no longer render
+    /// themselves instead of losing all of them on every render.
+    keeps_children: bool,
+    /// Values published with `provide_context`, keyed by their type.
+    contexts: BTreeMap<TypeId, Box<dyn Any + Send>>,
+    /// How many times the scope function has run.
+    #[cfg(any(feature = "devtools", test))]
+    render_count: usize,
+    /// The signals each effect read during its last run.
+    #[cfg(any(feature = "devtools", test))]
+    effect_reads: Vec<Vec<(usize, usize)>>,
 }
 
 impl ScopeData {
@@ -40,8 +129,17 @@ impl ScopeData {
             effects: Vec::new(),
             effect_cleanups: Vec::new(),
             effect_executing: Vec::new(),
+            effect_phases: Vec::new(),
             function: None,
             callback: None,
+            parent: None,
+            children: Vec::new(),
+            keeps_children: false,
+            contexts: BTreeMap::new(),
+            #[cfg(any(feature = "devtools", test))]
+            render_count: 0,
+            #[cfg(any(feature = "devtools", test))]
+            effect_reads: Vec::new(),
         }
     }
 
@@ -49,12 +147,22 @@ impl ScopeData {
     fn clear(&mut self) {
         self.signal_count = 0;
         self.effect_count = 0;
+        #[cfg(any(feature = "devtools", test))]
+        {
+            self.render_count = 0;
+            self.effect_reads.clear();
+        }
         self.signals.clear();
         self.effects.clear();
         self.effect_cleanups.clear();
         self.effect_executing.clear();
+        self.effect_phases.clear();
         self.function = None;
         self.callback = None;
+        self.parent = None;
+        self.children.clear();
+        self.keeps_children = false;
+        self.contexts.clear();
     }
 }
 
@@ -65,7 +173,14 @@ pub(crate) struct RuntimeState {
     current_scope: Option<usize>,
     rendering_scope: usize,
     next_scope_id: usize,
-    batch_updates: bool,
+    /// Ids of disposed scopes, handed out again before `next_scope_id` grows.
+    free_scope_ids: Vec<usize>,
+    /// Bumped every time a scope id is released so stale handles can be detected.
+    scope_generations: Vec<u32>,
+    /// Nesting depth of `batch` and `transaction`; renders wait until it is 0.
+    batch_depth: usize,
+    /// Undo steps recorded by each open transaction, innermost last.
+    transactions: Vec<Vec<Undo>>,
     /// True when inside run_scope_transient — skips cross-scope dep tracking.
     transient_scope: bool,
     /// Per-scope storage: indexed by scope_id - 1. O(1) access.
@@ -74,14 +189,55 @@ pub(crate) struct RuntimeState {
     scope_signal_changes: Vec<(usize, usize)>,
     /// Count of effects currently executing (for re-entrancy guard in set).
     executing_effects_count: usize,
+    /// How many `render_scope` calls are on the stack.
+    render_depth: usize,
+    /// Scopes, with their generation, whose layout and passive effects run
+    /// once the outermost render finishes, children before their parents.
+    queued_effects: Vec<(usize, u32)>,
     /// Cross-scope: which signals does a scope depend on.
     scope_dependencies: BTreeMap<usize, Vec<(usize, usize)>>,
     /// Cross-scope: which scopes depend on a signal.
     signal_dependencies: BTreeMap<(usize, usize), Vec<usize>>,
+    /// Cross-scope: which scopes depend on one part of a signal, by key.
+    keyed_dependencies: BTreeMap<(usize, usize), Vec<(DependencyKey, usize)>>,
+    /// Cross-scope: which signals a scope holds keyed dependencies on.
+    scope_keyed_dependencies: BTreeMap<usize, Vec<(usize, usize)>>,
     /// Scopes that need re-rendering.
     pending_scope_renders: Vec<usize>,
-    #[allow(dead_code)]
-    memo_cache: BTreeMap<String, Box<dyn SignalValue>>,
+    /// Derived node whose computation is running; reads become its sources.
+    tracking_node: Option<(usize, usize)>,
+    /// Effect that is running, by scope and index, so its reads can be shown
+    /// in [`graph_snapshot`].
+    #[cfg(any(feature = "devtools", test))]
+    running_effect: Option<(usize, usize)>,
+    /// True inside [`untrack`]; reads create no dependency edges.
+    untracked: bool,
+    /// Derived nodes that went stale since the last flush.
+    stale_nodes: Vec<(usize, usize)>,
+    /// Sources of selectors, with the key of the value they held last.
+    selectors: BTreeMap<(usize, usize), (SelectionKeyFn, u64)>,
+    /// Derived nodes created by [`create_watcher`], re-run as soon as they go
+    /// stale instead of when read.
+    watchers: BTreeSet<(usize, usize)>,
+    /// Streams created with `Signal::to_stream`, by signal.
+    #[cfg(feature = "futures")]
+    listeners: BTreeMap<(usize, usize), Vec<SignalListener>>,
+    /// Streams sent a value, woken once the lock is released.
+    #[cfg(feature = "futures")]
+    stream_wakers: Vec<Waker>,
+    /// Listeners of disposed signals, dropped once the lock is released
+    /// since that ends their streams.
+    #[cfg(feature = "futures")]
+    closed_listeners: Vec<SignalListener>,
+    /// Values recorded for the client by serialized resources, by key.
+    #[cfg(feature = "serde")]
+    hydration_state: BTreeMap<String, serde_json::Value>,
+    /// Values sent by the server that have not been picked up yet, by key.
+    #[cfg(feature = "serde")]
+    restored_state: BTreeMap<String, serde_json::Value>,
+    /// Signals created with `create_serialized_signal`, with their keys.
+    #[cfg(feature = "serde")]
+    serialized_signals: BTreeMap<(usize, usize), (String, SerializeFn)>,
 }
 
 impl RuntimeState {
@@ -90,15 +246,40 @@ impl RuntimeState {
             current_scope: None,
             rendering_scope: 0,
             next_scope_id: 1,
-            batch_updates: false,
+            free_scope_ids: Vec::new(),
+            scope_generations: Vec::new(),
+            batch_depth: 0,
+            transactions: Vec::new(),
             transient_scope: false,
             scopes: Vec::new(),
             scope_signal_changes: Vec::new(),
             executing_effects_count: 0,
+            render_depth: 0,
+            queued_effects: Vec::new(),
             scope_dependencies: BTreeMap::new(),
             signal_dependencies: BTreeMap::new(),
+            keyed_dependencies: BTreeMap::new(),
+            scope_keyed_dependencies: BTreeMap::new(),
             pending_scope_renders: Vec::new(),
-            memo_cache: BTreeMap::new(),
+            tracking_node: None,
+            #[cfg(any(feature = "devtools", test))]
+            running_effect: None,
+            untracked: false,
+            stale_nodes: Vec::new(),
+            selectors: BTreeMap::new(),
+            watchers: BTreeSet::new(),
+            #[cfg(feature = "futures")]
+            listeners: BTreeMap::new(),
+            #[cfg(feature = "futures")]
+            stream_wakers: Vec::new(),
+            #[cfg(feature = "futures")]
+            closed_listeners: Vec::new(),
+            #[cfg(feature = "serde")]
+            hydration_state: BTreeMap::new(),
+            #[cfg(feature = "serde")]
+            restored_state: BTreeMap::new(),
+            #[cfg(feature = "serde")]
+            serialized_signals: BTreeMap::new(),
         }
     }
 
@@ -120,6 +301,40 @@ impl RuntimeState {
         self.scopes[id - 1].get_or_insert_with(ScopeData::new)
     }
 
+    fn alloc_scope_id(&mut self) -> usize {
+        self.free_scope_ids.pop().unwrap_or_else(|| {
+            let id = self.next_scope_id;
+            self.next_scope_id += 1;
+            id
+        })
+    }
+
+    /// Called once a scope is gone. Everything keyed by the id is dropped too,
+    /// since the id will be handed to a new scope.
+    fn release_scope_id(&mut self, id: usize) {
+        if id > self.scope_generations.len() {
+            self.scope_generations.resize(id, 0);
+        }
+        self.scope_generations[id - 1] = self.scope_generations[id - 1].wrapping_add(1);
+        self.free_scope_ids.push(id);
+    }
+
+    #[inline]
+    fn scope_generation(&self, id: usize) -> u32 {
+        self.scope_generations.get(id - 1).copied().unwrap_or(0)
+    }
+
+    /// Number of ancestors above a scope; parents always have a smaller depth than their children.
+    fn scope_depth(&self, id: usize) -> usize {
+        let mut depth = 0;
+        let mut parent = self.scope(id).and_then(|s| s.parent);
+        while let Some(id) = parent {
+            depth += 1;
+            parent = self.scope(id).and_then(|s| s.parent);
+        }
+        depth
+    }
+
     #[inline]
     fn get_signal(&self, id: (usize, usize)) -> Option<&StoredValue> {
         self.scope(id.0).and_then(|s| s.signals.get(id.1 - 1))
@@ -136,90 +351,651 @@ impl RuntimeState {
         self.executing_effects_count > 0
     }
 
+    /// Record a change to a whole signal, queueing the scopes that read it
+    /// unless a render or effect picks them up itself. Returns true if pending
+    /// renders should be processed now.
+    ///
+    /// Only changes made during a render or effect go to
+    /// `scope_signal_changes`; ones made outside have queued their readers
+    /// already, and keeping them would queue those readers again after every
+    /// later render.
+    fn signal_changed(&mut self, id: (usize, usize)) -> bool {
+        self.mark_observers_stale(id);
+        self.update_selection(id);
+        #[cfg(feature = "futures")]
+        self.notify_listeners(id);
+        if !self.can_render() {
+            // Picked up by the render that is running, once it is done.
+            self.scope_signal_changes.push(id);
+            return false;
+        }
+        let scopes = self
+            .signal_dependencies
+            .get(&id)
+            .cloned()
+            .unwrap_or_default();
+        for scope_id in scopes {
+            self.push_pending_render(scope_id);
+        }
+        self.batch_depth == 0
+    }
+
+    /// Like [`RuntimeState::signal_changed`] for a change to one part of a
+    /// store, starting at item `from`.
+    fn part_changed(&mut self, signal: (usize, usize), path: &[u64], from: u64) -> bool {
+        self.mark_observers_stale(signal);
+        self.queue_keyed(signal, |key| stores::path_affects(path, from, key));
+        self.can_render() && self.batch_depth == 0
+    }
+
+    /// False while a scope renders or effects run; they flush changes when done.
+    fn can_render(&self) -> bool {
+        self.current_scope.is_none() && !self.has_executing_effects() && self.rendering_scope == 0
+    }
+
+    /// Keep `undo` in the innermost open transaction, if any.
+    fn record_undo(&mut self, undo: impl FnOnce(&mut RuntimeState) + Send + 'static) {
+        if let Some(journal) = self.transactions.last_mut() {
+            journal.push(Undo::Revert(Box::new(undo)));
+        }
+    }
+
+    /// Note in the innermost open transaction, if any, that `signal` was
+    /// changed in a way it cannot revert.
+    fn record_irreversible(&mut self, signal: (usize, usize)) {
+        if let Some(journal) = self.transactions.last_mut() {
+            journal.push(Undo::Irreversible(signal));
+        }
+    }
+
+    /// The scope that signals, effects and contexts created now belong to.
+    #[track_caller]
+    fn owner_scope(&self) -> usize {
+        self.current_scope
+            .unwrap_or_else(|| panic!("{}", SignalError::OutsideScope))
+    }
+
+    /// The stored value of `signal`, unless its scope has been disposed.
+    fn stored<T>(&self, signal: &Signal<T>) -> Result<&StoredValue, SignalError> {
+        let disposed = SignalError::Disposed {
+            signal: signal.id,
+            created_at: signal.created_at(),
+        };
+        if self.scope_generation(signal.id.0) != signal.generation {
+            return Err(disposed);
+        }
+        self.get_signal(signal.id).ok_or(disposed)
+    }
+
+    fn stored_mut<T>(&mut self, signal: &Signal<T>) -> Result<&mut StoredValue, SignalError> {
+        let disposed = SignalError::Disposed {
+            signal: signal.id,
+            created_at: signal.created_at(),
+        };
+        if self.scope_generation(signal.id.0) != signal.generation {
+            return Err(disposed);
+        }
+        self.get_signal_mut(signal.id).ok_or(disposed)
+    }
+
     #[inline]
     fn push_pending_render(&mut self, scope_id: usize) {
         if !self.pending_scope_renders.contains(&scope_id) {
             self.pending_scope_renders.push(scope_id);
         }
     }
+
+    /// Mark the direct observers of a changed value dirty and everything
+    /// downstream of them as needing a check. Nothing is recomputed here.
+    fn mark_observers_stale(&mut self, source: (usize, usize)) {
+        let mut stack: Vec<_> = match self.get_signal(source) {
+            Some(stored) if !stored.observers.is_empty() => stored
+                .observers
+                .iter()
+                .map(|&id| (id, NodeState::Dirty))
+                .collect(),
+            _ => return,
+        };
+
+        while let Some((id, state)) = stack.pop() {
+            let Some(stored) = self.get_signal_mut(id) else {
+                continue;
+            };
+            let Some(node) = stored.derived.as_mut() else {
+                continue;
+            };
+            if node.state >= state {
+                continue;
+            }
+            let was_clean = node.state == NodeState::Clean;
+            node.state = state;
+            if was_clean {
+                stack.extend(stored.observers.iter().map(|&o| (o, NodeState::Check)));
+                self.stale_nodes.push(id);
+            }
+        }
+    }
+
+    fn link_source(&mut self, observer: (usize, usize), source: (usize, usize)) {
+        match self
+            .get_signal_mut(observer)
+            .and_then(|s| s.derived.as_mut())
+        {
+            Some(node) if !node.sources.contains(&source) => node.sources.push(source),
+            _ => return,
+        }
+        if let Some(stored) = self.get_signal_mut(source) {
+            stored.observers.push(observer);
+        }
+    }
+
+    fn unlink_sources(&mut self, observer: (usize, usize)) {
+        let sources = match self
+            .get_signal_mut(observer)
+            .and_then(|s| s.derived.as_mut())
+        {
+            Some(node) => core::mem::take(&mut node.sources),
+            None => return,
+        };
+        for source in sources {
+            if let Some(stored) = self.get_signal_mut(source) {
+                stored.observers.retain(|o| *o != observer);
+            }
+        }
+    }
+
+    /// Record that the running effect, if any, read `signal`.
+    #[cfg(any(feature = "devtools", test))]
+    fn note_effect_read(&mut self, signal: (usize, usize)) {
+        let Some((scope_id, idx)) = self.running_effect else {
+            return;
+        };
+        let Some(scope) = self.scope_mut(scope_id) else {
+            return;
+        };
+        if scope.effect_reads.len() <= idx {
+            scope.effect_reads.resize_with(idx + 1, Vec::new);
+        }
+        let reads = &mut scope.effect_reads[idx];
+        if !reads.contains(&signal) {
+            reads.push(signal);
+        }
+    }
+
+    /// Subscribe the current scope to the part of a signal identified by `key`.
+    /// Derived nodes have no keyed edges and depend on the whole signal.
+    fn track_key(&mut self, signal: (usize, usize), key: &[u64]) {
+        if self.untracked {
+            return;
+        }
+        if let Some(observer) = self.tracking_node {
+            self.link_source(observer, signal);
+            return;
+        }
+        if self.transient_scope {
+            return;
+        }
+        let Some(scope_id) = self.current_scope else {
+            return;
+        };
+        #[cfg(any(feature = "devtools", test))]
+        self.note_effect_read(signal);
+        let deps = self.keyed_dependencies.entry(signal).or_default();
+        if !deps
+            .iter()
+            .any(|(k, s)| *s == scope_id && k.as_slice() == key)
+        {
+            deps.push((key.to_vec(), scope_id));
+        }
+        let signals = self.scope_keyed_dependencies.entry(scope_id).or_default();
+        if !signals.contains(&signal) {
+            signals.push(signal);
+        }
+    }
+
+    /// If `id` is the source of a selector, queue the scopes that selected the
+    /// old or the new value. Only called once the value changed, so that is
+    /// done even if both values hash to the same key.
+    fn update_selection(&mut self, id: (usize, usize)) {
+        let Some(&(key_of, previous)) = self.selectors.get(&id) else {
+            return;
+        };
+        let Some(current) = self
+            .get_signal(id)
+            .and_then(|stored| key_of(&*stored.value))
+        else {
+            return;
+        };
+        self.selectors.insert(id, (key_of, current));
+        self.queue_keyed(id, |key| key == [previous] || key == [current]);
+    }
+
+    /// Hand the new value of a signal to the streams created from it.
+    #[cfg(feature = "futures")]
+    fn notify_listeners(&mut self, id: (usize, usize)) {
+        let Some(mut listeners) = self.listeners.remove(&id) else {
+            return;
+        };
+        let mut wakers = Vec::new();
+        if let Some(stored) = self.get_signal(id) {
+            listeners.retain_mut(|listener| listener(&*stored.value, &mut wakers));
+        }
+        self.stream_wakers.append(&mut wakers);
+        if !listeners.is_empty() {
+            self.listeners.insert(id, listeners);
+        }
+    }
+
+    /// Returns true if a stream was created from the signal.
+    fn has_listeners(&self, id: (usize, usize)) -> bool {
+        #[cfg(feature = "futures")]
+        {
+            self.listeners.contains_key(&id)
+        }
+        #[cfg(not(feature = "futures"))]
+        {
+            let _ = id;
+            false
+        }
+    }
+
+    /// Queue a render for every scope whose key on `signal` is matched.
+    fn queue_keyed(&mut self, signal: (usize, usize), matches: impl Fn(&[u64]) -> bool) {
+        let scopes: Vec<_> = match self.keyed_dependencies.get(&signal) {
+            Some(deps) => deps
+                .iter()
+                .filter(|(key, _)| matches(key))
+                .map(|(_, scope_id)| *scope_id)
+                .collect(),
+            None => return,
+        };
+        for scope_id in scopes {
+            if scope_id != self.rendering_scope {
+                self.push_pending_render(scope_id);
+            }
+        }
+    }
+
+    fn untrack_scope_keys(&mut self, scope_id: usize) {
+        for signal in self
+            .scope_keyed_dependencies
+            .remove(&scope_id)
+            .unwrap_or_default()
+        {
+            if let Some(deps) = self.keyed_dependencies.get_mut(&signal) {
+                deps.retain(|(_, s)| *s != scope_id);
+            }
+        }
+    }
+
+    /// Called right before a scope's signals are dropped.
+    fn release_scope_signals(&mut self, scope_id: usize) {
+        self.unlink_scope_nodes(scope_id);
+        self.selectors.retain(|&(owner, _), _| owner != scope_id);
+        self.watchers.retain(|&(owner, _)| owner != scope_id);
+        #[cfg(feature = "futures")]
+        {
+            let owned: Vec<_> = self
+                .listeners
+                .range((scope_id, 0)..(scope_id + 1, 0))
+                .map(|(id, _)| *id)
+                .collect();
+            for id in owned {
+                let listeners = self.listeners.remove(&id).unwrap_or_default();
+                self.closed_listeners.extend(listeners);
+            }
+        }
+        #[cfg(feature = "serde")]
+        self.record_serialized_signals(Some(scope_id));
+    }
+
+    /// Write the current values of the serialized signals owned by a scope, or
+    /// of all of them, to the hydration state. A scope's signals are
+    /// unregistered as well, since they are about to be dropped.
+    #[cfg(feature = "serde")]
+    fn record_serialized_signals(&mut self, scope_id: Option<usize>) {
+        let range = match scope_id {
+            Some(id) => (id, 0)..(id + 1, 0),
+            None => (0, 0)..(usize::MAX, 0),
+        };
+        let recorded: Vec<_> = self
+            .serialized_signals
+            .range(range.clone())
+            .filter_map(|(id, (key, serialize))| {
+                let value = serialize(&*self.get_signal(*id)?.value)?;
+                Some((key.clone(), value))
+            })
+            .collect();
+        self.hydration_state.extend(recorded);
+        if scope_id.is_some() {
+            let ids: Vec<_> = self
+                .serialized_signals
+                .range(range)
+                .map(|(id, _)| *id)
+                .collect();
+            for id in ids {
+                self.serialized_signals.remove(&id);
+            }
+        }
+    }
+
+    /// Detach the derived nodes owned by a scope from the graph before its
+    /// signals are dropped.
+    fn unlink_scope_nodes(&mut self, scope_id: usize) {
+        let derived: Vec<_> = match self.scope(scope_id) {
+            Some(scope) => scope
+                .signals
+                .iter()
+                .enumerate()
+                .filter(|(_, s)| s.derived.is_some())
+                .map(|(idx, _)| (scope_id, idx + 1))
+                .collect(),
+            None => return,
+        };
+        for id in derived {
+            self.unlink_sources(id);
+        }
+        self.stale_nodes.retain(|(s, _)| *s != scope_id);
+    }
+}
+
+/// The state of one reactive runtime together with its lock-free scope flag.
+pub(crate) struct RuntimeCell {
+    state: Mutex<RuntimeState>,
+    /// Fast atomic flag for has_current_scope() — avoids locking the state just to check.
+    in_scope: AtomicBool,
+}
+
+impl RuntimeCell {
+    const fn new() -> Self {
+        Self {
+            state: Mutex::new(RuntimeState::new()),
+            in_scope: AtomicBool::new(false),
+        }
+    }
 }
 
-pub(crate) static RUNTIME: Mutex<RuntimeState> = Mutex::new(RuntimeState::new());
-/// Serializes SSR renders so the global runtime can be safely reset per request.
-static ISOLATED_RUNTIME_LOCK: Mutex<()> = Mutex::new(());
-/// Fast atomic flag for has_current_scope() — avoids locking RUNTIME just to check.
-static IN_SCOPE: AtomicBool = AtomicBool::new(false);
+/// Runtime used whenever no [`Runtime`] has been entered, e.g. by wasm apps.
+static GLOBAL_RUNTIME: RuntimeCell = RuntimeCell::new();
 
-pub trait SignalValue: Send {
-    fn as_any(&self) -> Option<&dyn Any>;
-    fn as_any_mut(&mut self) -> Option<&mut dyn Any>;
+#[cfg(feature = "std")]
+std::thread_local! {
+    static CURRENT_RUNTIME: core::cell::Cell<*const RuntimeCell> =
+        const { core::cell::Cell::new(core::ptr::null()) };
 }
 
-macro_rules! impl_signal_value {
-    ($($t:ty),*) => {
-        $(
-            impl SignalValue for $t {
-                #[inline]
-                fn as_any(&self) -> Option<&dyn Any> {
-                    Some(self)
-                }
-                #[inline]
-                fn as_any_mut(&mut self) -> Option<&mut dyn Any> {
-                    Some(self)
-                }
-            }
-        )*
-    };
+#[cfg(not(feature = "std"))]
+static CURRENT_RUNTIME: core::sync::atomic::AtomicPtr<RuntimeCell> =
+    core::sync::atomic::AtomicPtr::new(core::ptr::null_mut());
+
+#[cfg(feature = "std")]
+#[inline]
+fn current_runtime_ptr() -> *const RuntimeCell {
+    CURRENT_RUNTIME.with(|current| current.get())
 }
 
-impl_signal_value!(
-    String,
-    &'static str,
-    i8,
-    i16,
-    i32,
-    i64,
-    i128,
-    isize,
-    u8,
-    u16,
-    u32,
-    u64,
-    u128,
-    usize,
-    f32,
-    f64,
-    bool,
-    char,
-    ()
-);
+#[cfg(not(feature = "std"))]
+#[inline]
+fn current_runtime_ptr() -> *const RuntimeCell {
+    CURRENT_RUNTIME.load(AtomicOrdering::Acquire)
+}
+
+/// The runtime that was current when [`runtime`] was called. An entered
+/// runtime is kept alive for as long as this handle, or the [`RuntimeGuard`]
+/// locked from it, exists.
+enum CurrentRuntime {
+    Global,
+    Entered(Arc<RuntimeCell>),
+}
+
+impl CurrentRuntime {
+    /// Lock the runtime's state. The guard owns the handle, so the state
+    /// cannot outlive the runtime it belongs to.
+    #[inline]
+    fn lock(self) -> RuntimeGuard {
+        let cell: *const RuntimeCell = &*self;
+        // SAFETY: the global runtime is a static. An entered runtime is kept
+        // alive by the `Arc` moved into the guard below, which is dropped
+        // after the lock (fields drop in declaration order).
+        let state = unsafe { &*cell }.state.lock();
+        RuntimeGuard {
+            state,
+            _runtime: self,
+        }
+    }
+}
+
+impl core::ops::Deref for CurrentRuntime {
+    type Target = RuntimeCell;
+
+    #[inline]
+    fn deref(&self) -> &RuntimeCell {
+        match self {
+            CurrentRuntime::Global => &GLOBAL_RUNTIME,
+            CurrentRuntime::Entered(cell) => cell,
+        }
+    }
+}
+
+/// Locked state of the current runtime; see [`CurrentRuntime::lock`].
+struct RuntimeGuard {
+    state: spin::MutexGuard<'static, RuntimeState>,
+    _runtime: CurrentRuntime,
+}
+
+impl core::ops::Deref for RuntimeGuard {
+    type Target = RuntimeState;
+
+    #[inline]
+    fn deref(&self) -> &RuntimeState {
+        &self.state
+    }
+}
+
+impl core::ops::DerefMut for RuntimeGuard {
+    #[inline]
+    fn deref_mut(&mut self) -> &mut RuntimeState {
+        &mut self.state
+    }
+}
+
+/// Returns the runtime entered with [`Runtime::enter`], or the global runtime.
+#[inline]
+fn runtime() -> CurrentRuntime {
+    let ptr = current_runtime_ptr();
+    if ptr.is_null() {
+        CurrentRuntime::Global
+    } else {
+        // SAFETY: a non-null pointer was produced by `Arc::as_ptr` in
+        // `Runtime::enter`, which keeps that `Arc` alive while it is current.
+        unsafe {
+            Arc::increment_strong_count(ptr);
+            CurrentRuntime::Entered(Arc::from_raw(ptr))
+        }
+    }
+}
+
+/// An isolated reactive runtime.
+///
+/// By default every scope, signal and effect lives in one global runtime,
+/// which is what single-threaded wasm apps want. Servers can instead create a
+/// `Runtime` per request and run the render inside [`Runtime::enter`], so
+/// independent renders never share state or wait on each other.
+///
+/// With the `std` feature the entered runtime is tracked per thread, so many
+/// threads can each be inside their own runtime at once and runtimes can be
+/// nested. Without it, only one runtime can be entered at a time in the whole
+/// program; entering another one meanwhile fails with [`EnterError`].
+///
+/// Only reactive state is per runtime. The hooks installed with
+/// [`set_spawner`], [`set_error_hook`], [`set_timer`] and [`set_list_patcher`]
+/// are process-global and shared by every runtime.
+///
+/// # Example
+/// ```ignore
+/// use momenta_core::signals::{Runtime, create_signal, run_scope_transient};
+///
+/// let html = Runtime::new().enter(|| {
+///     run_scope_transient(|| create_signal(1).get().into(), |_| {}).to_html()
+/// });
+/// ```
+#[derive(Clone)]
+pub struct Runtime {
+    cell: Arc<RuntimeCell>,
+}
+
+impl Runtime {
+    pub fn new() -> Self {
+        Self {
+            cell: Arc::new(RuntimeCell::new()),
+        }
+    }
+
+    /// Returns the runtime entered on this thread, or `None` while the global
+    /// runtime is in use.
+    pub fn current() -> Option<Self> {
+        match runtime() {
+            CurrentRuntime::Global => None,
+            CurrentRuntime::Entered(cell) => Some(Self { cell }),
+        }
+    }
+
+    /// Run `f` with this runtime as the current one, restoring the previously
+    /// entered runtime afterwards (even if `f` panics).
+    ///
+    /// # Panics
+    /// Without the `std` feature, panics if a different runtime is already
+    /// entered; see [`Runtime::try_enter`].
+    pub fn enter<R>(&self, f: impl FnOnce() -> R) -> R {
+        match self.try_enter(f) {
+            Ok(result) => result,
+            Err(err) => panic!("{err}"),
+        }
+    }
+
+    /// Like [`Runtime::enter`], but returns an error instead of panicking
+    /// when the runtime cannot be entered. That only happens without the
+    /// `std` feature, while a different runtime is entered.
+    pub fn try_enter<R>(&self, f: impl FnOnce() -> R) -> Result<R, EnterError> {
+        enter_runtime_ptr(Arc::as_ptr(&self.cell), f)
+    }
+}
+
+/// Returned when a [`Runtime`] cannot be entered because a different one
+/// already is. Only possible without the `std` feature.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct EnterError;
 
-impl<T: SignalValue + 'static> SignalValue for alloc::vec::Vec<T> {
-    fn as_any(&self) -> Option<&dyn Any> {
-        Some(self)
+impl core::fmt::Display for EnterError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(
+            f,
+            "cannot enter a runtime while a different one is entered \
+             (nesting runtimes requires the `std` feature)"
+        )
     }
-    fn as_any_mut(&mut self) -> Option<&mut dyn Any> {
-        Some(self)
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for EnterError {}
+
+/// Make `ptr` the current runtime while `f` runs; null selects the global one.
+#[cfg(feature = "std")]
+fn enter_runtime_ptr<R>(ptr: *const RuntimeCell, f: impl FnOnce() -> R) -> Result<R, EnterError> {
+    struct EnterGuard(*const RuntimeCell);
+
+    impl Drop for EnterGuard {
+        fn drop(&mut self) {
+            CURRENT_RUNTIME.with(|current| current.set(self.0));
+        }
     }
+
+    let _guard = EnterGuard(CURRENT_RUNTIME.with(|current| current.replace(ptr)));
+    Ok(f())
 }
 
-impl<T: SignalValue + 'static> SignalValue for Option<T> {
-    fn as_any(&self) -> Option<&dyn Any> {
-        Some(self)
+/// Make `ptr` the current runtime while `f` runs; null selects the global one.
+///
+/// Without thread-local storage the current runtime is shared by the whole
+/// program, so it can only be switched away from the global one.
+#[cfg(not(feature = "std"))]
+fn enter_runtime_ptr<R>(ptr: *const RuntimeCell, f: impl FnOnce() -> R) -> Result<R, EnterError> {
+    struct EnterGuard;
+
+    impl Drop for EnterGuard {
+        fn drop(&mut self) {
+            CURRENT_RUNTIME.store(core::ptr::null_mut(), AtomicOrdering::Release);
+        }
+    }
+
+    if current_runtime_ptr() == ptr {
+        return Ok(f());
     }
-    fn as_any_mut(&mut self) -> Option<&mut dyn Any> {
-        Some(self)
+    CURRENT_RUNTIME
+        .compare_exchange(
+            core::ptr::null_mut(),
+            ptr.cast_mut(),
+            AtomicOrdering::AcqRel,
+            AtomicOrdering::Acquire,
+        )
+        .map_err(|_| EnterError)?;
+    let _guard = EnterGuard;
+    Ok(f())
+}
+
+impl Default for Runtime {
+    fn default() -> Self {
+        Self::new()
     }
 }
 
+/// A value that can be stored in a signal.
+///
+/// Implemented for every `'static + Send` type, e.g. numbers, `String`,
+/// tuples, arrays, `Box`, `Arc` of `Send + Sync` values, collections and
+/// plain user structs, so no derive is needed. It has no methods and cannot
+/// be implemented by hand; signals store their values as `dyn Any` and
+/// downcast them directly.
+///
+/// Types that are not `Send` cannot be stored:
+/// ```compile_fail
+/// use momenta_core::signals::SignalValue;
+///
+/// fn assert_signal_value<T: SignalValue>() {}
+/// assert_signal_value::<std::rc::Rc<u32>>();
+/// ```
+pub trait SignalValue: Any + Send {}
+
+impl<T: Any + Send> SignalValue for T {}
+
 #[derive(Debug)]
 pub struct Signal<T> {
     id: (usize, usize),
+    /// Generation of the owning scope, to tell a disposed signal from a new
+    /// one that reuses its id.
+    generation: u32,
+    /// Where the signal was created, reported in its errors.
+    #[cfg(debug_assertions)]
+    created_at: &'static Location<'static>,
     _marker: PhantomData<T>,
 }
 
 impl<T> Copy for Signal<T> {}
 
+impl<T> Signal<T> {
+    /// Where the signal was created; only known in debug builds.
+    fn created_at(&self) -> Option<&'static Location<'static>> {
+        #[cfg(debug_assertions)]
+        {
+            Some(self.created_at)
+        }
+        #[cfg(not(debug_assertions))]
+        {
+            None
+        }
+    }
+}
+
 impl<T: SignalValue + Not<Output = bool> + Clone + 'static> Not for Signal<T> {
     type Output = bool;
     fn not(self) -> Self::Output {
@@ -411,1105 +1187,4719 @@ impl<T: SignalValue + Clone + 'static> Signal<Vec<T>> {
     }
 }
 
-impl<T: SignalValue + 'static> Signal<T> {
-    /// Map the signal value to a new computed signal
-    ///
-    /// # Example
-    /// ```rust,no_run
-    /// use momenta_core::signals::create_signal;
-    ///
-    /// let count = create_signal(5);
-    /// let doubled = count.derive(|&x| x * 2);
-    /// assert_eq!(doubled.get(), 10);
-    /// ```
-    #[cfg(any(feature = "computed", feature = "full-reactivity"))]
-    pub fn derive<U, F>(&self, f: F) -> Signal<U>
-    where
-        T: Clone + PartialEq,
-        U: SignalValue + PartialEq + Clone + 'static,
-        F: Fn(&T) -> U + Send + 'static,
-    {
-        let self_clone = *self;
-        create_computed(move || self_clone.with(|val| f(val)).unwrap())
-    }
-
-    pub fn with<R>(&self, f: impl FnOnce(&T) -> R) -> Option<R> {
-        let mut rt = RUNTIME.lock();
-
-        // Skip dependency tracking in transient scopes
-        if !rt.transient_scope {
-            if let Some(current_scope) = rt.current_scope {
-                let deps = rt.signal_dependencies.entry(self.id).or_default();
-                if !deps.contains(&current_scope) {
-                    deps.push(current_scope);
-                }
-                let scope_deps = rt.scope_dependencies.entry(current_scope).or_default();
-                if !scope_deps.contains(&self.id) {
-                    scope_deps.push(self.id);
-                }
+macro_rules! impl_map_signal {
+    ($map:ident, $($key_bound:tt)+) => {
+        impl<K, V> Signal<$map<K, V>>
+        where
+            K: SignalValue + $($key_bound)+ + 'static,
+            V: SignalValue + PartialEq + 'static,
+        {
+            /// Returns the previous value. Inserting a value equal to the
+            /// current one is not a change.
+            pub fn insert(&self, key: K, value: V) -> Option<V>
+            where
+                K: Clone,
+                V: Clone,
+            {
+                self.write_value(|map, _, journal| {
+                    if map.get(&key) == Some(&value) {
+                        return (Some(value), false, None);
+                    }
+                    let undo_key = journal.then(|| key.clone());
+                    let previous = map.insert(key, value);
+                    let revert = undo_key.map(|key| Self::restore_entry(key, previous.clone()));
+                    (previous, true, revert)
+                })
+                .ok()
+                .flatten()
             }
-        }
 
-        rt.get_signal(self.id)
-            .and_then(|stored| {
-                stored
-                    .value
-                    .as_any()
-                    .and_then(|any| any.downcast_ref::<T>())
-            })
+            pub fn remove(&self, key: &K) -> Option<V>
+            where
+                V: Clone,
+            {
+                self.write_value(|map, _, journal| match map.remove_entry(key) {
+                    Some((key, removed)) => {
+                        let revert =
+                            journal.then(|| Self::restore_entry(key, Some(removed.clone())));
+                        (Some(removed), true, revert)
+                    }
+                    None => (None, false, None),
+                })
+                .ok()
+                .flatten()
+            }
+
+            /// Update the value under `key`, inserting `default()` first if
+            /// there is none. Dependents are notified only if the entry changed.
+            pub fn update_entry(
+                &self,
+                key: K,
+                default: impl FnOnce() -> V,
+                f: impl FnOnce(&mut V),
+            ) where
+                K: Clone,
+                V: Clone,
+            {
+                let _ = self.write_value(|map, _, journal| match map.get_mut(&key) {
+                    Some(value) => {
+                        let before = value.clone();
+                        f(value);
+                        if *value == before {
+                            return ((), false, None);
+                        }
+                        ((), true, journal.then(|| Self::restore_entry(key, Some(before))))
+                    }
+                    None => {
+                        let mut value = default();
+                        f(&mut value);
+                        let revert = journal.then(|| Self::restore_entry(key.clone(), None));
+                        map.insert(key, value);
+                        ((), true, revert)
+                    }
+                });
+            }
+
+            /// Keep only the entries `f` returns true for. Dependents are
+            /// notified if an entry was removed or `f` changed a value.
+            pub fn retain(&self, mut f: impl FnMut(&K, &mut V) -> bool)
+            where
+                K: Clone,
+                V: Clone,
+            {
+                let _ = self.write_value(|map, _, journal| {
+                    let mut changed = false;
+                    let mut previous = Vec::new();
+                    map.retain(|key, value| {
+                        let before = value.clone();
+                        let keep = f(key, value);
+                        if !keep || *value != before {
+                            changed = true;
+                            if journal {
+                                previous.push((key.clone(), before));
+                            }
+                        }
+                        keep
+                    });
+                    let revert = journal.then(|| {
+                        Revert::Restore(Box::new(move |map: &mut $map<K, V>| {
+                            map.extend(previous);
+                        }))
+                    });
+                    ((), changed, revert)
+                });
+            }
+
+            pub fn clear(&self) {
+                self.take_and_clear();
+            }
+
+            /// A clone of the value under `key`.
+            pub fn get_value(&self, key: &K) -> Option<V>
+            where
+                V: Clone,
+            {
+                self.with(|map| map.get(key).cloned()).flatten()
+            }
+
+            pub fn contains_key(&self, key: &K) -> bool {
+                self.with(|map| map.contains_key(key)).unwrap_or(false)
+            }
+
+            pub fn len(&self) -> usize {
+                self.with(|map| map.len()).unwrap_or(0)
+            }
+
+            pub fn is_empty(&self) -> bool {
+                self.with(|map| map.is_empty()).unwrap_or(true)
+            }
+
+            /// Puts `previous` back under `key`, or removes the entry if there
+            /// was none.
+            fn restore_entry(key: K, previous: Option<V>) -> Revert<$map<K, V>> {
+                Revert::Restore(Box::new(move |map| match previous {
+                    Some(value) => {
+                        map.insert(key, value);
+                    }
+                    None => {
+                        map.remove(&key);
+                    }
+                }))
+            }
+        }
+    };
+}
+
+macro_rules! impl_set_signal {
+    ($set:ident, $($bound:tt)+) => {
+        impl<T> Signal<$set<T>>
+        where
+            T: SignalValue + $($bound)+ + 'static,
+        {
+            /// Returns true if the item was not in the set yet.
+            pub fn insert(&self, item: T) -> bool
+            where
+                T: Clone,
+            {
+                self.write_value(|set, _, journal| {
+                    let undo_item = journal.then(|| item.clone());
+                    if !set.insert(item) {
+                        return (false, false, None);
+                    }
+                    let revert = undo_item.map(|item| {
+                        Revert::Restore(Box::new(move |set: &mut $set<T>| {
+                            set.remove(&item);
+                        }))
+                    });
+                    (true, true, revert)
+                })
+                .unwrap_or(false)
+            }
+
+            /// Returns true if the item was in the set.
+            pub fn remove(&self, item: &T) -> bool {
+                self.write_value(|set, _, journal| match set.take(item) {
+                    Some(removed) => {
+                        let revert = journal.then(|| {
+                            Revert::Restore(Box::new(move |set: &mut $set<T>| {
+                                set.insert(removed);
+                            }))
+                        });
+                        (true, true, revert)
+                    }
+                    None => (false, false, None),
+                })
+                .unwrap_or(false)
+            }
+
+            pub fn retain(&self, mut f: impl FnMut(&T) -> bool)
+            where
+                T: Clone,
+            {
+                let _ = self.write_value(|set, _, journal| {
+                    let mut removed = Vec::new();
+                    let len = set.len();
+                    set.retain(|item| {
+                        let keep = f(item);
+                        if !keep && journal {
+                            removed.push(item.clone());
+                        }
+                        keep
+                    });
+                    let revert = journal.then(|| {
+                        Revert::Restore(Box::new(move |set: &mut $set<T>| {
+                            set.extend(removed);
+                        }))
+                    });
+                    ((), set.len() != len, revert)
+                });
+            }
+
+            pub fn clear(&self) {
+                self.take_and_clear();
+            }
+
+            pub fn contains(&self, item: &T) -> bool {
+                self.with(|set| set.contains(item)).unwrap_or(false)
+            }
+
+            pub fn len(&self) -> usize {
+                self.with(|set| set.len()).unwrap_or(0)
+            }
+
+            pub fn is_empty(&self) -> bool {
+                self.with(|set| set.is_empty()).unwrap_or(true)
+            }
+        }
+    };
+}
+
+impl_map_signal!(BTreeMap, Ord);
+impl_set_signal!(BTreeSet, Ord);
+#[cfg(feature = "std")]
+impl_map_signal!(HashMap, Eq + core::hash::Hash);
+#[cfg(feature = "std")]
+impl_set_signal!(HashSet, Eq + core::hash::Hash);
+
+impl<T: SignalValue + 'static> Signal<T> {
+    /// Map the signal value to a new computed signal
+    ///
+    /// # Example
+    /// ```rust,no_run
+    /// use momenta_core::signals::create_signal;
+    ///
+    /// let count = create_signal(5);
+    /// let doubled = count.derive(|&x| x * 2);
+    /// assert_eq!(doubled.get(), 10);
+    /// ```
+    #[cfg(any(feature = "computed", feature = "full-reactivity"))]
+    pub fn derive<U, F>(&self, f: F) -> Signal<U>
+    where
+        T: Clone + PartialEq,
+        U: SignalValue + PartialEq + Clone + 'static,
+        F: Fn(&T) -> U + Send + 'static,
+    {
+        let self_clone = *self;
+        create_computed(move || self_clone.with(|val| f(val)).unwrap())
+    }
+
+    /// Returns `None` if the signal can no longer be read, see
+    /// [`Signal::try_with`].
+    pub fn with<R>(&self, f: impl FnOnce(&T) -> R) -> Option<R> {
+        self.read(true, f).ok()
+    }
+
+    /// Like [`Signal::with`], but without subscribing the current scope or
+    /// computation to the signal.
+    pub fn with_untracked<R>(&self, f: impl FnOnce(&T) -> R) -> Option<R> {
+        self.read(false, f).ok()
+    }
+
+    /// Like [`Signal::with`], but reports why the value could not be read.
+    pub fn try_with<R>(&self, f: impl FnOnce(&T) -> R) -> Result<R, SignalError> {
+        self.read(true, f)
+    }
+
+    fn read<R>(&self, track: bool, f: impl FnOnce(&T) -> R) -> Result<R, SignalError> {
+        let mut rt = runtime().lock();
+        rt.stored(self)?;
+
+        let is_stale = rt
+            .get_signal(self.id)
+            .and_then(|s| s.derived.as_ref())
+            .is_some_and(|node| node.state != NodeState::Clean);
+        if is_stale {
+            drop(rt);
+            update_derived(self.id);
+            rt = runtime().lock();
+        }
+
+        if track && !rt.untracked {
+            if let Some(observer) = rt.tracking_node {
+                rt.link_source(observer, self.id);
+            } else if !rt.transient_scope {
+                // Skip scope dependency tracking in transient scopes
+                if let Some(current_scope) = rt.current_scope {
+                    #[cfg(any(feature = "devtools", test))]
+                    rt.note_effect_read(self.id);
+                    let deps = rt.signal_dependencies.entry(self.id).or_default();
+                    if !deps.contains(&current_scope) {
+                        deps.push(current_scope);
+                    }
+                    let scope_deps = rt.scope_dependencies.entry(current_scope).or_default();
+                    if !scope_deps.contains(&self.id) {
+                        scope_deps.push(self.id);
+                    }
+                }
+            }
+        }
+
+        let value = &rt.stored(self)?.value;
+        if value.is::<InUse>() {
+            return Err(SignalError::InUse {
+                signal: self.id,
+                created_at: self.created_at(),
+            });
+        }
+        value
+            .downcast_ref::<T>()
             .map(f)
+            .ok_or(SignalError::TypeMismatch {
+                signal: self.id,
+                expected: core::any::type_name::<T>(),
+                created_at: self.created_at(),
+            })
     }
 
+    /// Replace the value. Dependents are not notified if the new value is
+    /// equal to the current one, by `PartialEq` or the function given to
+    /// [`create_signal_with_eq`].
+    ///
+    /// Does nothing if the signal can no longer be written, see
+    /// [`Signal::try_set`]. Debug builds log why.
     #[inline]
-    pub fn set(&self, value: T)
+    pub fn set(&self, value: T) {
+        if let Err(error) = self.try_set(value) {
+            warn_ignored_write(&error);
+        }
+    }
+
+    /// Like [`Signal::set`], but reports why the value could not be written.
+    pub fn try_set(&self, value: T) -> Result<(), SignalError> {
+        self.write_value(|current, eq, journal| {
+            if eq.is_some_and(|eq| eq(current, &value)) {
+                return ((), false, None);
+            }
+            let previous = core::mem::replace(current, value);
+            ((), true, journal.then(|| restore(previous)))
+        })
+    }
+
+    /// Change the value in place and notify dependents, without comparing it
+    /// to the old one, e.g. to push onto a large list without cloning it.
+    ///
+    /// A [`transaction`] cannot roll this back, since that would take a copy
+    /// of the value; if one that ran `update` fails, the signal keeps its new
+    /// value and [`ReactiveError::PartialRollback`] is reported. Use
+    /// [`Signal::update_if_changed`] inside transactions instead.
+    ///
+    /// `f` may read other signals; this one fails with [`SignalError::InUse`]
+    /// until `f` returns.
+    ///
+    /// # Example
+    /// ```ignore
+    /// let log = create_signal(Vec::new());
+    /// log.update(|lines| lines.push("started".to_string()));
+    /// ```
+    pub fn update(&self, f: impl FnOnce(&mut T)) {
+        let written = self.write_value(|current, _, journal| {
+            f(current);
+            ((), true, journal.then(irreversible))
+        });
+        if let Err(error) = written {
+            warn_ignored_write(&error);
+        }
+    }
+
+    /// Change the value in place, notifying dependents only if `eq` reports
+    /// the old and the new value as different. Returns whether they were.
+    pub fn update_if_changed(&self, f: impl FnOnce(&mut T), eq: impl FnOnce(&T, &T) -> bool) -> bool
     where
-        T: PartialEq,
+        T: Clone,
     {
-        let should_process = {
-            let mut rt = RUNTIME.lock();
-            let stored = match rt.get_signal_mut(self.id) {
-                Some(s) => s,
-                None => return,
-            };
+        self.write_value(|current, _, journal| {
+            let previous = current.clone();
+            f(current);
+            let changed = !eq(&previous, current);
+            (changed, changed, journal.then(|| restore(previous)))
+        })
+        .unwrap_or(false)
+    }
 
-            // Try in-place update: avoids Box reallocation for same-type values
-            if let Some(current) = stored
-                .value
-                .as_any_mut()
-                .and_then(|any| any.downcast_mut::<T>())
-            {
-                if *current == value {
-                    return;
-                }
-                *current = value;
-            } else {
-                return;
+    /// Replace the value with its default, notifying dependents unless it was
+    /// empty already. Inside a transaction the old value is kept to restore.
+    fn take_and_clear(&self)
+    where
+        T: Default + PartialEq,
+    {
+        let _ = self.write_value(|current, _, journal| {
+            if *current == T::default() {
+                return ((), false, None);
             }
+            let previous = core::mem::take(current);
+            ((), true, journal.then(|| restore(previous)))
+        });
+    }
+
+    /// Update the value in place. `f` reports whether it changed anything, and
+    /// dependents are only notified if it did. Inside a transaction the value
+    /// is cloned first so the update can be rolled back.
+    fn modify<R>(&self, f: impl FnOnce(&mut T) -> (R, bool)) -> Option<R>
+    where
+        T: Clone,
+    {
+        self.write_value(|current, _, journal| {
+            let previous = journal.then(|| current.clone());
+            let (result, changed) = f(current);
+            (result, changed, previous.map(restore))
+        })
+        .ok()
+    }
+
+    /// Apply `f` to the value. It is given the function set with
+    /// [`create_signal_with_eq`], if any, and told whether a transaction is
+    /// open, and returns its result, whether the value changed and, in a
+    /// transaction, how to revert the change.
+    ///
+    /// The value is taken out of the runtime while `f` runs, so user code
+    /// called by `f` and the old values it drops never run under the lock.
+    /// Reading or writing the signal from that code fails with
+    /// [`SignalError::InUse`]. If `f` panics, the value is put back as `f`
+    /// left it.
+    fn write_value<R>(
+        &self,
+        f: impl FnOnce(&mut T, Option<&SignalEq>, bool) -> (R, bool, Option<Revert<T>>),
+    ) -> Result<R, SignalError> {
+        let (mut taken, journal) = {
+            let mut rt = runtime().lock();
+            let journal = !rt.transactions.is_empty();
+            let stored = rt.stored_mut(self)?;
+            if stored.value.is::<InUse>() {
+                return Err(SignalError::InUse {
+                    signal: self.id,
+                    created_at: self.created_at(),
+                });
+            }
+            if !stored.value.is::<T>() {
+                return Err(SignalError::TypeMismatch {
+                    signal: self.id,
+                    expected: core::any::type_name::<T>(),
+                    created_at: self.created_at(),
+                });
+            }
+            (TakenValue::take(self.id, self.generation, stored), journal)
+        };
 
-            rt.scope_signal_changes.push(self.id);
+        let current = taken
+            .value
+            .as_mut()
+            .and_then(|value| value.downcast_mut::<T>())
+            .expect("checked before taking it");
+        let (result, changed, revert) = f(current, taken.eq.as_ref(), journal);
+        let (value, eq) = taken.into_parts();
 
-            let should_process = !rt.batch_updates
-                && rt.current_scope.is_none()
-                && !rt.has_executing_effects()
-                && rt.rendering_scope == 0;
+        let should_process = {
+            let mut rt = runtime().lock();
+            // If `f` disposed the signal, the value is dropped once the lock
+            // is released.
+            let Ok(stored) = rt.stored_mut(self) else {
+                return Ok(result);
+            };
+            stored.value = value;
+            stored.eq = eq;
+            if !changed {
+                return Ok(result);
+            }
 
-            if should_process {
-                let scopes_to_queue: Vec<_> = rt
-                    .signal_dependencies
-                    .get(&self.id)
-                    .map(|deps| deps.iter().copied().collect())
-                    .unwrap_or_default();
-                for scope_id in scopes_to_queue {
-                    rt.push_pending_render(scope_id);
+            match revert {
+                Some(Revert::Restore(revert)) => {
+                    let signal = *self;
+                    rt.record_undo(move |rt| {
+                        let current = rt
+                            .stored_mut(&signal)
+                            .ok()
+                            .and_then(|stored| stored.value.downcast_mut::<T>());
+                        if let Some(current) = current {
+                            revert(current);
+                            rt.signal_changed(signal.id);
+                        }
+                    });
                 }
+                Some(Revert::Irreversible) => rt.record_irreversible(self.id),
+                None => {}
             }
-            should_process
+            rt.signal_changed(self.id)
         };
 
+        wake_streams();
         if should_process {
             process_pending_renders();
         }
+        Ok(result)
     }
 
+    /// # Panics
+    /// If the signal can no longer be read, see [`Signal::try_get`].
     #[inline]
+    #[track_caller]
     pub fn get(&self) -> T
     where
         T: Clone,
     {
-        self.with(|val| val.clone()).unwrap()
+        self.try_get().unwrap_or_else(|err| panic!("{err}"))
     }
-}
-
-struct StoredValue {
-    value: Box<dyn SignalValue>,
-}
-
-/// Run a function with batched updates enabled
-/// All signal updates within the function will be batched and applied at the end
-///
-/// # Example
-/// ```ignore
-/// use momenta_core::signals::{create_signal, batch};
-///
-/// let count = create_signal(0);
-/// let text = create_signal("hello");
-///
-/// batch(|| {
-///     count.set(1);  // Won't trigger re-render yet
-///     count.set(2);  // Won't trigger re-render yet
-///     text.set("world");  // Won't trigger re-render yet
-/// }); // All changes applied and single re-render triggered here
-/// ```
-pub fn batch<F, R>(f: F) -> R
-where
-    F: FnOnce() -> R,
-{
-    RUNTIME.lock().batch_updates = true;
-
-    let result = f();
 
-    RUNTIME.lock().batch_updates = false;
+    /// Like [`Signal::get`], but reports why the value could not be read
+    /// instead of panicking.
+    #[inline]
+    pub fn try_get(&self) -> Result<T, SignalError>
+    where
+        T: Clone,
+    {
+        self.read(true, T::clone)
+    }
 
-    // Process all pending renders
-    process_pending_renders();
+    /// Read the value without subscribing the current scope or computation.
+    #[inline]
+    #[track_caller]
+    pub fn get_untracked(&self) -> T
+    where
+        T: Clone,
+    {
+        self.read(false, T::clone)
+            .unwrap_or_else(|err| panic!("{err}"))
+    }
 
-    result
+    /// Shorthand for [`Signal::get_untracked`].
+    #[inline]
+    pub fn peek(&self) -> T
+    where
+        T: Clone,
+    {
+        self.get_untracked()
+    }
 }
 
-/// Create a memoized computation that caches results based on dependencies
-///
-/// The computation is only re-run when dependencies change. Results are cached.
-///
-/// # Example
-/// ```rust,no_run
-/// use momenta_core::signals::{create_signal, create_memo};
-///
-/// let count = create_signal(5);
-/// let expensive = create_memo(
-///     "double_count",
-///     move || {
-///         // Expensive computation
-///         count.get() * 2
-///     }
-/// );
-/// ```
-#[cfg(any(feature = "memoization", feature = "full-reactivity"))]
-pub fn create_memo<T, F>(key: &str, computation: F) -> Signal<T>
-where
-    T: SignalValue + PartialEq + Clone + 'static,
-    F: Fn() -> T + Send + 'static,
-{
-    let (scope_id, signal_id) = {
-        let mut rt = RUNTIME.lock();
-        let scope_id = rt
-            .current_scope
-            .expect("Signals can only be created within a scope context");
-        let scope = rt.ensure_scope(scope_id);
-        scope.signal_count += 1;
-        (scope_id, scope.signal_count)
-    };
-    let signal = Signal {
-        id: (scope_id, signal_id),
-        _marker: PhantomData,
-    };
+/// Stands in for the value of a signal while it is taken out to be updated.
+struct InUse;
 
-    let cache_key = alloc::format!("{}-{}", key, scope_id);
+/// Holds a signal's value while it is taken out to be updated, and puts it
+/// back if the update panics so the signal is not left [`InUse`].
+struct TakenValue {
+    signal: (usize, usize),
+    generation: u32,
+    value: Option<Box<dyn Any + Send>>,
+    eq: Option<SignalEq>,
+}
 
-    {
-        let mut rt = RUNTIME.lock();
-        let cached_val = rt.memo_cache.get(&cache_key).and_then(|cached| {
-            cached
-                .as_any()
-                .and_then(|any| any.downcast_ref::<T>())
-                .cloned()
-        });
-        if let Some(cached_val) = cached_val {
-            let scope = rt.ensure_scope(scope_id);
-            let idx = signal_id - 1;
-            if idx >= scope.signals.len() {
-                scope.signals.push(StoredValue {
-                    value: Box::new(cached_val),
-                });
-            } else {
-                scope.signals[idx] = StoredValue {
-                    value: Box::new(cached_val),
-                };
-            }
-            return signal;
+impl TakenValue {
+    /// Take the value out of `stored`, which belongs to `signal`.
+    fn take(signal: (usize, usize), generation: u32, stored: &mut StoredValue) -> Self {
+        Self {
+            signal,
+            generation,
+            value: Some(core::mem::replace(&mut stored.value, Box::new(InUse))),
+            eq: stored.eq.take(),
         }
     }
 
-    let initial_value = computation();
-    {
-        let mut rt = RUNTIME.lock();
-        rt.memo_cache
-            .insert(cache_key.clone(), Box::new(initial_value.clone()));
-        let scope = rt.ensure_scope(scope_id);
-        let idx = signal_id - 1;
-        if idx >= scope.signals.len() {
-            scope.signals.push(StoredValue {
-                value: Box::new(initial_value),
-            });
-        } else {
-            scope.signals[idx] = StoredValue {
-                value: Box::new(initial_value),
-            };
-        }
+    /// The value and equality function, to be put back by the caller.
+    fn into_parts(mut self) -> (Box<dyn Any + Send>, Option<SignalEq>) {
+        let value = self.value.take().expect("only taken once");
+        (value, self.eq.take())
     }
+}
 
-    let signal_clone = signal;
-    create_effect(move || {
-        let new_value = computation();
+impl Drop for TakenValue {
+    fn drop(&mut self) {
+        let Some(value) = self.value.take() else {
+            return;
+        };
+        let mut rt = runtime().lock();
+        if rt.scope_generation(self.signal.0) != self.generation {
+            return;
+        }
+        if let Some(stored) = rt.get_signal_mut(self.signal)
+            && stored.value.is::<InUse>()
         {
-            let mut rt = RUNTIME.lock();
-            rt.memo_cache
-                .insert(cache_key.clone(), Box::new(new_value.clone()));
+            stored.value = value;
+            if let Some(eq) = self.eq.take() {
+                stored.eq = Some(eq);
+            }
         }
-        signal_clone.set(new_value);
-    });
-
-    signal
+    }
 }
 
-pub enum SignalInit<T> {
-    Value(T),
-    InitFn(Box<dyn Fn() -> T + Send + 'static>),
+struct StoredValue {
+    value: Box<dyn Any + Send>,
+    /// Derived nodes that read this value during their last computation.
+    observers: Vec<(usize, usize)>,
+    derived: Option<Box<DerivedNode>>,
+    /// Used by `set` to skip values equal to the current one.
+    eq: Option<SignalEq>,
 }
 
-impl<T: SignalValue> From<T> for SignalInit<T> {
-    fn from(value: T) -> Self {
-        SignalInit::Value(value)
+impl StoredValue {
+    fn new(value: Box<dyn Any + Send>) -> Self {
+        Self {
+            value,
+            observers: Vec::new(),
+            derived: None,
+            eq: None,
+        }
     }
 }
 
-#[derive(Debug)]
-pub enum SignalCreationError {
-    OutsideScope,
+/// Freshness of a derived node, ordered from fresh to stale.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+enum NodeState {
+    /// The cached value is up to date.
+    Clean,
+    /// Something upstream changed; the sources have to be brought up to date
+    /// before we know whether this node needs to run.
+    Check,
+    /// A direct source changed, the value must be recomputed.
+    Dirty,
 }
 
-impl core::fmt::Display for SignalCreationError {
-    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
-        write!(f, "Signals can only be created within a scope context")
+/// The extra state carried by computed and memoized signals.
+struct DerivedNode {
+    /// Taken out while the computation runs.
+    compute: Option<ComputeFn>,
+    eq: ValueEq,
+    state: NodeState,
+    /// Signals read during the last computation.
+    sources: Vec<(usize, usize)>,
+}
+
+fn values_equal<T: PartialEq + 'static>(a: &dyn Any, b: &dyn Any) -> bool {
+    match (a.downcast_ref::<T>(), b.downcast_ref::<T>()) {
+        (Some(a), Some(b)) => a == b,
+        _ => false,
     }
 }
 
-/// Create new signal within current scope
-#[inline]
-pub fn create_signal<T, I>(init: I) -> Signal<T>
-where
-    T: SignalValue + PartialEq + 'static,
-    I: Into<SignalInit<T>>,
-{
-    let mut rt = RUNTIME.lock();
-    let scope_id = rt
-        .current_scope
-        .ok_or(SignalCreationError::OutsideScope)
-        .unwrap();
+/// Create a derived node in the current scope. The node starts dirty and is
+/// only computed once something reads it. On re-render the existing node and
+/// its cached value are kept, and the new closure is used from the next
+/// recomputation on.
+#[track_caller]
+fn create_derived<T>(compute: ComputeFn, eq: ValueEq) -> Signal<T> {
+    let mut rt = runtime().lock();
+    let scope_id = rt.owner_scope();
     let scope = rt.ensure_scope(scope_id);
     scope.signal_count += 1;
     let signal_id = scope.signal_count;
-    let idx = signal_id - 1;
-    let signal = Signal {
+
+    match scope.signals.get_mut(signal_id - 1) {
+        Some(stored) => {
+            if let Some(node) = stored.derived.as_mut() {
+                node.compute = Some(compute);
+            }
+        }
+        None => scope.signals.push(StoredValue {
+            value: Box::new(()),
+            observers: Vec::new(),
+            derived: Some(Box::new(DerivedNode {
+                compute: Some(compute),
+                eq,
+                state: NodeState::Dirty,
+                sources: Vec::new(),
+            })),
+            eq: None,
+        }),
+    }
+
+    Signal {
         id: (scope_id, signal_id),
+        generation: rt.scope_generation(scope_id),
+        #[cfg(debug_assertions)]
+        created_at: Location::caller(),
         _marker: PhantomData,
+    }
+}
+
+/// Bring a derived node up to date. Sources are refreshed first, in
+/// dependency order, and the node itself only runs if one of them actually
+/// changed, so it never observes a half-updated graph.
+fn update_derived(id: (usize, usize)) {
+    let sources = {
+        let rt = runtime().lock();
+        match rt.get_signal(id).and_then(|s| s.derived.as_ref()) {
+            Some(node) if node.state == NodeState::Check => node.sources.clone(),
+            Some(node) if node.state == NodeState::Dirty => Vec::new(),
+            _ => return,
+        }
     };
 
-    // If signal already exists (re-render), reuse it
-    if idx < scope.signals.len() {
-        return signal;
+    for source in sources {
+        update_derived(source);
+        let rt = runtime().lock();
+        let is_dirty = rt
+            .get_signal(id)
+            .and_then(|s| s.derived.as_ref())
+            .is_some_and(|node| node.state == NodeState::Dirty);
+        if is_dirty {
+            break;
+        }
     }
 
-    let initial_value = match init.into() {
-        SignalInit::Value(v) => v,
-        SignalInit::InitFn(f) => {
-            drop(rt);
-            let val = f();
-            rt = RUNTIME.lock();
-            val
+    let is_dirty = {
+        let mut rt = runtime().lock();
+        match rt.get_signal_mut(id).and_then(|s| s.derived.as_mut()) {
+            Some(node) if node.state == NodeState::Dirty => true,
+            Some(node) => {
+                node.state = NodeState::Clean;
+                false
+            }
+            None => false,
         }
     };
-    let scope = rt.ensure_scope(scope_id);
-    scope.signals.push(StoredValue {
-        value: Box::new(initial_value),
+    if is_dirty {
+        recompute_derived(id);
+    }
+}
+
+fn recompute_derived(id: (usize, usize)) {
+    let (compute, previous) = {
+        let mut rt = runtime().lock();
+        rt.unlink_sources(id);
+        let Some(node) = rt.get_signal_mut(id).and_then(|s| s.derived.as_mut()) else {
+            return;
+        };
+        // Cleared before running so a source changing mid-computation marks
+        // the node dirty again instead of being lost.
+        node.state = NodeState::Clean;
+        let Some(compute) = node.compute.take() else {
+            return;
+        };
+        let previous_tracking = rt.tracking_node.replace(id);
+        // A computation always tracks its own reads, even when it is pulled
+        // from inside `untrack`.
+        let previous_untracked = core::mem::replace(&mut rt.untracked, false);
+        (compute, (previous_tracking, previous_untracked))
+    };
+
+    let value = compute();
+
+    let mut rt = runtime().lock();
+    (rt.tracking_node, rt.untracked) = previous;
+    let Some(stored) = rt.get_signal_mut(id) else {
+        return;
+    };
+    let Some(node) = stored.derived.as_mut() else {
+        return;
+    };
+    if node.compute.is_none() {
+        node.compute = Some(compute);
+    }
+    if (node.eq)(&*stored.value, &*value) {
+        return;
+    }
+    stored.value = value;
+
+    rt.mark_observers_stale(id);
+    rt.update_selection(id);
+    #[cfg(feature = "futures")]
+    rt.notify_listeners(id);
+    let rendering_scope = rt.rendering_scope;
+    let scopes = rt.signal_dependencies.get(&id).cloned().unwrap_or_default();
+    for scope_id in scopes {
+        if scope_id != rendering_scope {
+            rt.push_pending_render(scope_id);
+        }
+    }
+    drop(rt);
+    wake_streams();
+}
+
+/// Pull every stale derived node that a scope reads, so scopes are only
+/// queued for derived values that actually changed.
+fn flush_stale_nodes() {
+    for _ in 0..MAX_FLUSH_ITERATIONS {
+        let stale: Vec<_> = {
+            let mut rt = runtime().lock();
+            if rt.stale_nodes.is_empty() {
+                return;
+            }
+            let stale = core::mem::take(&mut rt.stale_nodes);
+            stale
+                .into_iter()
+                .filter(|id| {
+                    rt.signal_dependencies
+                        .get(id)
+                        .is_some_and(|scopes| !scopes.is_empty())
+                        || rt.selectors.contains_key(id)
+                        || rt.has_listeners(*id)
+                        || rt.watchers.contains(id)
+                })
+                .collect()
+        };
+        for id in stale {
+            if runtime().lock().watchers.contains(&id) {
+                run_watcher(id);
+            } else {
+                update_derived(id);
+            }
+        }
+    }
+    report_flush_loop();
+}
+
+/// Drop the stale nodes left after [`MAX_FLUSH_ITERATIONS`] rounds and report
+/// them. They are recomputed the next time something reads them.
+#[cold]
+fn report_flush_loop() {
+    let signals = {
+        let mut rt = runtime().lock();
+        let mut signals = core::mem::take(&mut rt.stale_nodes);
+        if signals.is_empty() {
+            return;
+        }
+        signals.sort_unstable();
+        signals.dedup();
+        signals
+    };
+    report_error(&ReactiveError::FlushLoop {
+        iterations: MAX_FLUSH_ITERATIONS,
+        signals,
     });
+}
 
-    signal
+/// Wake the streams sent a value, and end those of disposed signals, while
+/// the runtime was locked.
+fn wake_streams() {
+    #[cfg(feature = "futures")]
+    {
+        let (wakers, closed) = {
+            let mut rt = runtime().lock();
+            if rt.stream_wakers.is_empty() && rt.closed_listeners.is_empty() {
+                return;
+            }
+            (
+                core::mem::take(&mut rt.stream_wakers),
+                core::mem::take(&mut rt.closed_listeners),
+            )
+        };
+        drop(closed);
+        for waker in wakers {
+            waker.wake();
+        }
+    }
 }
 
-/// Create a computed/derived signal that automatically updates based on dependencies
+/// Run a function without tracking the signals it reads.
 ///
-/// The computation function is re-run whenever any signal it reads changes.
+/// Reads inside `f` do not subscribe the current scope or computation, so an
+/// effect can react to one signal while looking at others. Scopes rendered and
+/// computations run inside `f` still track their own reads.
 ///
 /// # Example
-/// ```rust,no_run
-/// use momenta_core::signals::{create_signal, create_computed};
+/// ```ignore
+/// use momenta_core::signals::{create_effect, create_signal, untrack};
 ///
-/// let count = create_signal(0);
-/// let doubled = create_computed(move || count.get() * 2);
+/// let query = create_signal(String::new());
+/// let page = create_signal(1);
 ///
-/// assert_eq!(doubled.get(), 0);
-/// count.set(5);
-/// assert_eq!(doubled.get(), 10);
+/// create_effect(move || {
+///     // Re-runs when `query` changes, but not when `page` does.
+///     let query = query.get();
+///     let page = untrack(|| page.get());
+///     println!("searching {query} on page {page}");
+/// });
 /// ```
-#[cfg(any(feature = "computed", feature = "full-reactivity"))]
-pub fn create_computed<T, F>(computation: F) -> Signal<T>
-where
-    T: SignalValue + PartialEq + Clone + 'static,
-    F: Fn() -> T + Send + 'static,
-{
-    let (scope_id, signal_id) = {
-        let mut rt = RUNTIME.lock();
-        let scope_id = rt
-            .current_scope
-            .expect("Signals can only be created within a scope context");
-        let scope = rt.ensure_scope(scope_id);
-        scope.signal_count += 1;
-        (scope_id, scope.signal_count)
-    };
-    let signal = Signal {
-        id: (scope_id, signal_id),
-        _marker: PhantomData,
-    };
+pub fn untrack<R>(f: impl FnOnce() -> R) -> R {
+    let _guard = UntrackGuard(core::mem::replace(&mut runtime().lock().untracked, true));
+    f()
+}
 
-    let initial_value = computation();
-    {
-        let mut rt = RUNTIME.lock();
-        let scope = rt.ensure_scope(scope_id);
-        scope.signals.push(StoredValue {
-            value: Box::new(initial_value),
-        });
+/// Puts back the tracking state from before [`untrack`], even if `f` panics.
+struct UntrackGuard(bool);
+
+impl Drop for UntrackGuard {
+    fn drop(&mut self) {
+        runtime().lock().untracked = self.0;
     }
+}
 
-    let signal_clone = signal;
-    create_effect(move || {
-        let new_value = computation();
-        signal_clone.set(new_value);
-    });
+/// Run `f` now, and again whenever a signal it read has changed.
+///
+/// Unlike an effect, the reads only subscribe the watcher, so the owning scope
+/// does not re-render. The watcher is kept across re-renders, running the
+/// closure passed last, and goes away with its scope.
+fn create_watcher(f: impl Fn() + Send + 'static) {
+    let node = create_derived::<()>(
+        Box::new(move || {
+            f();
+            Box::new(())
+        }),
+        |_, _| true,
+    );
+    runtime().lock().watchers.insert(node.id);
+    update_derived(node.id);
+}
 
-    signal
+/// Re-run a stale watcher. Like an effect, the renders its writes cause are
+/// queued instead of run from inside it.
+fn run_watcher(id: (usize, usize)) {
+    let start = {
+        let mut rt = runtime().lock();
+        rt.executing_effects_count += 1;
+        rt.scope_signal_changes.len()
+    };
+    update_derived(id);
+    let mut rt = runtime().lock();
+    rt.executing_effects_count -= 1;
+    let start = start.min(rt.scope_signal_changes.len());
+    for signal in rt.scope_signal_changes.split_off(start) {
+        let scopes = rt
+            .signal_dependencies
+            .get(&signal)
+            .cloned()
+            .unwrap_or_default();
+        for scope_id in scopes {
+            rt.push_pending_render(scope_id);
+        }
+    }
 }
 
-/// Create effect that runs when dependencies change
+/// Call `callback` with the new and the previous value whenever `source`
+/// changes.
+///
+/// Unlike an effect, the callback does not run for the initial value, and
+/// neither it nor the scope calling `watch` is subscribed to anything, so a
+/// change does not re-render the scope.
 ///
 /// # Example
 /// ```ignore
-/// use momenta_core::signals::{create_signal, create_effect};
+/// use momenta_core::signals::{create_signal, watch};
 ///
 /// let count = create_signal(0);
-/// create_effect(move || {
-///     println!("Count changed to: {}", count.get());
-/// });
+/// watch(count, |new, old| println!("count went from {old} to {new}"));
 /// ```
-#[inline]
-pub fn create_effect(effect: impl Fn() + Send + 'static) {
-    let mut rt = RUNTIME.lock();
-    let scope_id = rt
-        .current_scope
-        .ok_or(SignalCreationError::OutsideScope)
-        .unwrap();
-    let is_transient = rt.transient_scope;
-    let scope = rt.ensure_scope(scope_id);
-    scope.effect_count += 1;
-    scope.effects.push(Some(Box::new(effect)));
-    if !is_transient {
-        scope.effect_cleanups.push(None);
-        scope.effect_executing.push(false);
-    }
+pub fn watch<T, F>(source: Signal<T>, callback: F)
+where
+    T: SignalValue + PartialEq + Clone + 'static,
+    F: Fn(&T, &T) + Send + 'static,
+{
+    let previous = create_signal(source.get_untracked());
+    create_watcher(move || {
+        let Some(current) = source.with(T::clone) else {
+            return;
+        };
+        let old = previous.get_untracked();
+        if current != old {
+            previous.set(current.clone());
+            untrack(|| callback(&current, &old));
+        }
+    });
 }
 
-/// Create effect with cleanup function
-///
-/// The cleanup function is called when the scope is destroyed or before the effect re-runs.
+/// Run a function with batched updates enabled
+/// All signal updates within the function will be batched and applied at the end
 ///
 /// # Example
 /// ```ignore
-/// use momenta_core::signals::{create_signal, create_effect_with_cleanup};
+/// use momenta_core::signals::{create_signal, batch};
 ///
 /// let count = create_signal(0);
-/// create_effect_with_cleanup(
-///     move || {
-///         println!("Setting up for count: {}", count.get());
-///         // Return cleanup function
-///         move || {
-///             println!("Cleaning up");
-///         }
-///     }
-/// );
+/// let text = create_signal("hello");
+///
+/// batch(|| {
+///     count.set(1);  // Won't trigger re-render yet
+///     count.set(2);  // Won't trigger re-render yet
+///     text.set("world");  // Won't trigger re-render yet
+/// }); // All changes applied and single re-render triggered here
 /// ```
-pub fn create_effect_with_cleanup<C>(effect: impl Fn() -> C + Send + 'static)
+pub fn batch<F, R>(f: F) -> R
 where
-    C: FnOnce() + Send + 'static,
+    F: FnOnce() -> R,
 {
-    let (scope_id, effect_idx) = {
-        let mut rt = RUNTIME.lock();
-        let scope_id = rt
-            .current_scope
-            .ok_or(SignalCreationError::OutsideScope)
-            .unwrap();
-        let scope = rt.ensure_scope(scope_id);
-        scope.effect_count += 1;
-        let idx = scope.effect_count - 1;
-        (scope_id, idx)
+    let guard = BatchGuard::open(false);
+    let result = f();
+    core::mem::forget(guard);
+
+    let outermost = {
+        let mut rt = runtime().lock();
+        rt.batch_depth -= 1;
+        rt.batch_depth == 0
     };
 
-    let mut rt = RUNTIME.lock();
-    let scope = rt.ensure_scope(scope_id);
-    scope.effects.push(Some(Box::new(move || {
-        {
-            let mut rt = RUNTIME.lock();
-            if let Some(scope) = rt.scope_mut(scope_id) {
-                if let Some(cleanup) = scope
-                    .effect_cleanups
-                    .get_mut(effect_idx)
-                    .and_then(|c| c.take())
-                {
-                    drop(rt);
-                    cleanup();
+    // Process all pending renders once the outermost batch is done
+    if outermost {
+        process_pending_renders();
+    }
+
+    result
+}
+
+/// Run `f` as a batch and roll back every signal and store it modified if it
+/// returns `Err`, e.g. to undo an optimistic update after a failed request.
+///
+/// Transactions nest: a failed inner transaction only undoes its own changes,
+/// while a failed outer one also undoes what its inner transactions committed.
+/// Dependents are notified once, when the outermost batch or transaction ends.
+///
+/// Updates made in place keep what they need to be undone, e.g. the items
+/// `clear` removed or the entries `insert` on a map signal replaced. Ones that
+/// cannot without a copy of the whole value, such as [`Signal::update`], keep
+/// their new value on rollback and are reported as [`ReactiveError::PartialRollback`]; use
+/// [`Signal::update_if_changed`] for those inside a transaction.
+///
+/// # Example
+/// ```ignore
+/// use momenta_core::signals::{create_signal, transaction};
+///
+/// let likes = create_signal(10);
+///
+/// let saved = transaction(|| {
+///     likes.set(11); // Shown right away once the transaction commits...
+///     api::like_post()
+/// });
+/// // ...or put back to 10 if the request failed.
+/// ```
+pub fn transaction<R, E>(f: impl FnOnce() -> Result<R, E>) -> Result<R, E> {
+    let guard = BatchGuard::open(true);
+    let result = f();
+    core::mem::forget(guard);
+
+    let mut kept = Vec::new();
+    let outermost = {
+        let mut rt = runtime().lock();
+        let journal = rt.transactions.pop().unwrap_or_default();
+        match (&result, rt.transactions.last_mut()) {
+            (Ok(_), Some(outer)) => outer.extend(journal),
+            (Ok(_), None) => {}
+            (Err(_), _) => {
+                for undo in journal.into_iter().rev() {
+                    match undo {
+                        Undo::Revert(undo) => undo(&mut rt),
+                        Undo::Irreversible(signal) => kept.push(signal),
+                    }
                 }
             }
         }
+        rt.batch_depth -= 1;
+        rt.batch_depth == 0
+    };
 
-        let new_cleanup = effect();
-        {
-            let mut rt = RUNTIME.lock();
-            if let Some(scope) = rt.scope_mut(scope_id) {
-                if effect_idx < scope.effect_cleanups.len() {
-                    scope.effect_cleanups[effect_idx] = Some(Box::new(new_cleanup));
+    wake_streams();
+    if !kept.is_empty() {
+        kept.sort_unstable();
+        kept.dedup();
+        report_error(&ReactiveError::PartialRollback { signals: kept });
+    }
+    if outermost {
+        process_pending_renders();
+    }
+
+    result
+}
+
+/// Closes a batch, rolling back its transaction if it has one, when the
+/// closure given to [`batch`] or [`transaction`] panics. Forgotten once the
+/// closure returns. Renders left pending are done by the next flush.
+struct BatchGuard {
+    transaction: bool,
+}
+
+impl BatchGuard {
+    fn open(transaction: bool) -> Self {
+        let mut rt = runtime().lock();
+        rt.batch_depth += 1;
+        if transaction {
+            rt.transactions.push(Vec::new());
+        }
+        Self { transaction }
+    }
+}
+
+impl Drop for BatchGuard {
+    fn drop(&mut self) {
+        let mut rt = runtime().lock();
+        if self.transaction {
+            let journal = rt.transactions.pop().unwrap_or_default();
+            for undo in journal.into_iter().rev() {
+                if let Undo::Revert(undo) = undo {
+                    undo(&mut rt);
                 }
             }
         }
-    })));
-    let scope = rt.ensure_scope(scope_id);
-    scope.effect_cleanups.push(None);
-    scope.effect_executing.push(false);
+        rt.batch_depth -= 1;
+        drop(rt);
+        wake_streams();
+    }
 }
 
-pub fn run_scope(
-    scope_fn: impl FnMut() -> Node + Send + 'static,
-    callback: impl Fn(&Node) + Send + Sync + 'static,
-) -> Node {
-    let scope_id = {
-        let mut rt = RUNTIME.lock();
-        let id = rt.next_scope_id;
-        rt.next_scope_id += 1;
-        let scope = rt.ensure_scope(id);
-        scope.function = Some(Box::new(scope_fn));
-        scope.callback = Some(Arc::new(callback));
-        id
-    };
+/// One write made inside a transaction.
+enum Undo {
+    /// Reverts the write.
+    Revert(Box<dyn FnOnce(&mut RuntimeState) + Send>),
+    /// A write that kept no copy of the old value, by signal.
+    Irreversible((usize, usize)),
+}
 
-    render_scope(scope_id)
+/// How to revert an update made inside a transaction.
+enum Revert<T> {
+    /// Puts the value back the way it was before the update.
+    Restore(Box<dyn FnOnce(&mut T) + Send>),
+    /// The update kept no copy of the old value.
+    Irreversible,
 }
 
-/// Run a scope once and dispose all reactive state created for it afterwards.
-///
-/// Optimized path for transient scopes: avoids storing the scope function and
-/// callback in the runtime since they won't be needed for re-renders, and
-/// skips re-entrancy checks and signal change detection.
-#[inline]
-pub fn run_scope_transient(
-    scope_fn: impl FnOnce() -> Node + Send + 'static,
-    callback: impl Fn(&Node) + Send + Sync + 'static,
-) -> Node {
-    let (scope_id, previous_scope, was_transient) = {
-        let mut rt = RUNTIME.lock();
-        let id = rt.next_scope_id;
-        rt.next_scope_id += 1;
-        rt.ensure_scope(id);
-        let prev = rt.current_scope;
-        let was_transient = rt.transient_scope;
-        rt.current_scope = Some(id);
-        rt.transient_scope = true;
-        IN_SCOPE.store(true, AtomicOrdering::Relaxed);
-        (id, prev, was_transient)
-    };
+fn restore<T: Send + 'static>(previous: T) -> Revert<T> {
+    Revert::Restore(Box::new(move |value| *value = previous))
+}
 
-    let node = scope_fn();
+fn irreversible<T>() -> Revert<T> {
+    Revert::Irreversible
+}
 
-    callback(&node);
+/// Create a memoized computation that caches results based on dependencies
+///
+/// The computation is only re-run when dependencies change. The result is kept
+/// in the memo's slot of its scope, so it survives re-renders of that scope
+/// and is dropped with it.
+///
+/// # Example
+/// ```rust,no_run
+/// use momenta_core::signals::{create_signal, create_memo};
+///
+/// let count = create_signal(5);
+/// let expensive = create_memo(move || {
+///     // Expensive computation
+///     count.get() * 2
+/// });
+/// ```
+#[cfg(any(feature = "memoization", feature = "full-reactivity"))]
+#[track_caller]
+pub fn create_memo<T, F>(computation: F) -> Signal<T>
+where
+    T: SignalValue + PartialEq + Clone + 'static,
+    F: Fn() -> T + Send + 'static,
+{
+    create_derived::<T>(
+        Box::new(move || Box::new(computation()) as Box<dyn Any + Send>),
+        values_equal::<T>,
+    )
+}
 
-    // Single-lock fast path when there are no effects (common case).
-    let effects: Vec<ScopeEffect> = {
-        let mut rt = RUNTIME.lock();
-        let has_effects = rt
-            .scope(scope_id)
-            .map(|s| s.effect_count > 0)
-            .unwrap_or(false);
-        if has_effects {
-            rt.executing_effects_count += 1;
-            let scope = rt.scope_mut(scope_id).unwrap();
-            scope.effects.iter_mut().filter_map(|e| e.take()).collect()
-        } else {
-            // No effects: do full teardown in this single lock acquisition
-            rt.current_scope = previous_scope;
-            rt.transient_scope = was_transient;
-            IN_SCOPE.store(previous_scope.is_some(), AtomicOrdering::Relaxed);
-            if let Some(scope) = rt.scope_mut(scope_id) {
-                scope.clear();
-            }
-            rt.scope_signal_changes.clear();
-            rt.next_scope_id = scope_id;
-            return node;
-        }
-    };
+/// A cache of computed values that outlives re-created scopes.
+///
+/// A memo is tied to the scope that created it, so list rows that are rendered
+/// as fresh child scopes recompute their memos on every render. Create a cache
+/// in the long-lived parent scope instead and look values up by a key of your
+/// choosing, e.g. the row's id. The key must cover everything the value is
+/// computed from; the cache does not track dependencies.
+///
+/// With a capacity, the least recently used entries are dropped once the cache
+/// holds more than that many. The cache is dropped with its scope either way.
+///
+/// # Example
+/// ```rust,no_run
+/// use momenta_core::signals::create_memo_cache;
+///
+/// let labels = create_memo_cache::<u32, String>(Some(100));
+/// for id in 0..3 {
+///     let label = labels.get_or_compute(id, || format!("Row {id}"));
+/// }
+/// ```
+#[cfg(any(feature = "memoization", feature = "full-reactivity"))]
+#[derive(Debug)]
+pub struct MemoCache<K, V> {
+    state: Signal<MemoCacheState<K, V>>,
+}
 
-    for effect in &effects {
-        effect();
+#[cfg(any(feature = "memoization", feature = "full-reactivity"))]
+impl<K, V> Copy for MemoCache<K, V> {}
+
+#[cfg(any(feature = "memoization", feature = "full-reactivity"))]
+impl<K, V> Clone for MemoCache<K, V> {
+    fn clone(&self) -> Self {
+        *self
     }
+}
 
-    {
-        let mut rt = RUNTIME.lock();
-        rt.executing_effects_count -= 1;
-        rt.current_scope = previous_scope;
-        rt.transient_scope = was_transient;
-        IN_SCOPE.store(previous_scope.is_some(), AtomicOrdering::Relaxed);
-        if let Some(scope) = rt.scope_mut(scope_id) {
-            scope.clear();
+#[cfg(any(feature = "memoization", feature = "full-reactivity"))]
+#[derive(Debug, Clone)]
+struct MemoCacheState<K, V> {
+    /// Each value with the tick it was last used at.
+    entries: BTreeMap<K, (V, u64)>,
+    clock: u64,
+    capacity: Option<usize>,
+}
+
+#[cfg(any(feature = "memoization", feature = "full-reactivity"))]
+impl<K, V> MemoCache<K, V>
+where
+    K: SignalValue + Ord + Clone + 'static,
+    V: SignalValue + Clone + 'static,
+{
+    /// The value cached under `key`, computing and caching it first if there
+    /// is none. `compute` runs outside the runtime lock, so it may read and
+    /// create signals.
+    pub fn get_or_compute(&self, key: K, compute: impl FnOnce() -> V) -> V {
+        let cached = self.state.modify(|state| {
+            state.clock += 1;
+            let clock = state.clock;
+            let cached = state.entries.get_mut(&key).map(|(value, last_used)| {
+                *last_used = clock;
+                value.clone()
+            });
+            (cached, false)
+        });
+        if let Some(Some(value)) = cached {
+            return value;
         }
 
-        rt.scope_signal_changes.clear();
+        let value = compute();
+        self.state.modify(|state| {
+            state.clock += 1;
+            let clock = state.clock;
+            state.entries.insert(key, (value.clone(), clock));
+            if let Some(capacity) = state.capacity {
+                while state.entries.len() > capacity {
+                    let oldest = state
+                        .entries
+                        .iter()
+                        .min_by_key(|(_, (_, last_used))| *last_used)
+                        .map(|(key, _)| key.clone());
+                    match oldest {
+                        Some(oldest) => state.entries.remove(&oldest),
+                        None => break,
+                    };
+                }
+            }
+            ((), false)
+        });
+        value
+    }
+
+    /// The number of cached values. Zero once the cache's scope is disposed.
+    pub fn len(&self) -> usize {
+        self.state
+            .with_untracked(|state| state.entries.len())
+            .unwrap_or(0)
+    }
 
-        rt.next_scope_id = scope_id;
+    /// Whether the cache holds no values.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
     }
 
-    node
+    /// Drop every cached value.
+    pub fn clear(&self) {
+        self.state.modify(|state| {
+            state.entries.clear();
+            ((), false)
+        });
+    }
 }
 
-/// Runs a closure with a freshly reset runtime and clears all runtime state again afterwards.
-pub fn with_isolated_runtime<R>(f: impl FnOnce() -> R) -> R {
-    let _guard = ISOLATED_RUNTIME_LOCK.lock();
-    *RUNTIME.lock() = RuntimeState::new();
-    IN_SCOPE.store(false, AtomicOrdering::Relaxed);
-    let result = f();
-    *RUNTIME.lock() = RuntimeState::new();
-    IN_SCOPE.store(false, AtomicOrdering::Relaxed);
-    result
+/// Create a [`MemoCache`] in the current scope, holding at most `capacity`
+/// values if one is given.
+#[cfg(any(feature = "memoization", feature = "full-reactivity"))]
+#[track_caller]
+pub fn create_memo_cache<K, V>(capacity: Option<usize>) -> MemoCache<K, V>
+where
+    K: SignalValue + Ord + Clone + 'static,
+    V: SignalValue + Clone + 'static,
+{
+    // The cache is only written through `modify`, which never notifies.
+    let state = MemoCacheState {
+        entries: BTreeMap::new(),
+        clock: 0,
+        capacity,
+    };
+    MemoCache {
+        state: create_signal_with_eq(state, |_, _| false),
+    }
 }
 
-#[allow(dead_code)]
-pub(crate) fn get_current_scope() -> Option<usize> {
-    RUNTIME.lock().current_scope
+pub enum SignalInit<T> {
+    Value(T),
+    InitFn(Box<dyn Fn() -> T + Send + 'static>),
 }
 
-/// Returns true when code is currently executing inside a reactive scope.
-#[inline]
-pub fn has_current_scope() -> bool {
-    IN_SCOPE.load(AtomicOrdering::Relaxed)
+impl<T: SignalValue> From<T> for SignalInit<T> {
+    fn from(value: T) -> Self {
+        SignalInit::Value(value)
+    }
 }
 
-#[allow(dead_code)]
-fn clear_scope_effects(scope_id: usize) {
-    let cleanups_to_call = {
-        let mut rt = RUNTIME.lock();
-        let mut cleanups = Vec::new();
-        if let Some(scope) = rt.scope_mut(scope_id) {
-            for eff in scope.effects.iter_mut() {
-                *eff = None;
+/// Why a signal could not be created, read or written.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SignalError {
+    /// There is no scope to create the signal, effect or context in.
+    OutsideScope,
+    /// The scope that created the signal has been disposed, e.g. because the
+    /// signal was captured by a closure that outlived its component.
+    Disposed {
+        signal: (usize, usize),
+        /// Where the signal was created; only known in debug builds.
+        created_at: Option<&'static Location<'static>>,
+    },
+    /// The signal holds a value of another type than the one requested.
+    TypeMismatch {
+        signal: (usize, usize),
+        expected: &'static str,
+        created_at: Option<&'static Location<'static>>,
+    },
+    /// The signal is being updated, e.g. it was used from inside the closure
+    /// given to its own [`Signal::update`] or [`create_signal_with_eq`].
+    InUse {
+        signal: (usize, usize),
+        created_at: Option<&'static Location<'static>>,
+    },
+    /// The part of a store a [`Lens`] points to does not exist, e.g. an item
+    /// past the end of a list.
+    MissingPart {
+        signal: (usize, usize),
+        created_at: Option<&'static Location<'static>>,
+    },
+}
+
+impl core::fmt::Display for SignalError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        let created_at = match self {
+            SignalError::OutsideScope => {
+                return write!(f, "Signals can only be created within a scope context");
             }
-            for cleanup in scope.effect_cleanups.iter_mut() {
-                if let Some(c) = cleanup.take() {
-                    cleanups.push(c);
-                }
+            SignalError::Disposed { signal, created_at } => {
+                write!(
+                    f,
+                    "Signal {} of scope {} was used after its scope was disposed",
+                    signal.1, signal.0
+                )?;
+                created_at
+            }
+            SignalError::TypeMismatch {
+                signal,
+                expected,
+                created_at,
+            } => {
+                write!(
+                    f,
+                    "Signal {} of scope {} does not hold a value of type `{expected}`",
+                    signal.1, signal.0
+                )?;
+                created_at
             }
+            SignalError::InUse { signal, created_at } => {
+                write!(
+                    f,
+                    "Signal {} of scope {} was used while it was being updated",
+                    signal.1, signal.0
+                )?;
+                created_at
+            }
+            SignalError::MissingPart { signal, created_at } => {
+                write!(
+                    f,
+                    "Store {} of scope {} has no value at the path of the lens",
+                    signal.1, signal.0
+                )?;
+                created_at
+            }
+        };
+        match created_at {
+            Some(location) => write!(f, " (created at {location})"),
+            None => Ok(()),
         }
-        cleanups
+    }
+}
+
+/// Tell the developer about a write that failed silently; debug builds only.
+#[inline]
+fn warn_ignored_write(error: &SignalError) {
+    #[cfg(all(debug_assertions, feature = "wasm", target_arch = "wasm32"))]
+    web_sys::console::warn_1(&alloc::format!("momenta: {error}").into());
+    #[cfg(all(
+        debug_assertions,
+        feature = "std",
+        not(all(feature = "wasm", target_arch = "wasm32"))
+    ))]
+    std::eprintln!("momenta: {error}");
+    let _ = error;
+}
+
+#[deprecated(note = "use `SignalError` instead")]
+pub type SignalCreationError = SignalError;
+
+/// Create new signal within current scope
+///
+/// # Panics
+/// Outside of a scope. Use [`try_create_signal`] to handle that case.
+#[inline]
+#[track_caller]
+pub fn create_signal<T, I>(init: I) -> Signal<T>
+where
+    T: SignalValue + PartialEq + 'static,
+    I: Into<SignalInit<T>>,
+{
+    try_create_signal(init).unwrap_or_else(|err| panic!("{err}"))
+}
+
+/// Like [`create_signal`], but returns [`SignalError::OutsideScope`] instead
+/// of panicking when there is no current scope.
+#[track_caller]
+pub fn try_create_signal<T, I>(init: I) -> Result<Signal<T>, SignalError>
+where
+    T: SignalValue + PartialEq + 'static,
+    I: Into<SignalInit<T>>,
+{
+    create_signal_inner(init, Box::new(values_equal::<T>))
+}
+
+/// Create a signal that uses `eq` instead of `PartialEq` to decide whether
+/// [`Signal::set`] changes it, e.g. for types without `PartialEq` or with an
+/// expensive one. `eq` may read other signals, but not this one, see
+/// [`SignalError::InUse`].
+///
+/// # Example
+/// ```ignore
+/// use momenta_core::signals::create_signal_with_eq;
+///
+/// // Documents are equal when their revisions are.
+/// let document = create_signal_with_eq(Document::default(), |a, b| a.revision == b.revision);
+/// ```
+#[track_caller]
+pub fn create_signal_with_eq<T, I>(
+    init: I,
+    eq: impl Fn(&T, &T) -> bool + Send + 'static,
+) -> Signal<T>
+where
+    T: SignalValue + 'static,
+    I: Into<SignalInit<T>>,
+{
+    let eq = move |a: &dyn Any, b: &dyn Any| match (a.downcast_ref::<T>(), b.downcast_ref::<T>()) {
+        (Some(a), Some(b)) => eq(a, b),
+        _ => false,
+    };
+    create_signal_inner(init, Box::new(eq)).unwrap_or_else(|err| panic!("{err}"))
+}
+
+#[track_caller]
+fn create_signal_inner<T, I>(init: I, eq: SignalEq) -> Result<Signal<T>, SignalError>
+where
+    T: SignalValue + 'static,
+    I: Into<SignalInit<T>>,
+{
+    let mut rt = runtime().lock();
+    let scope_id = rt.current_scope.ok_or(SignalError::OutsideScope)?;
+    let generation = rt.scope_generation(scope_id);
+    let scope = rt.ensure_scope(scope_id);
+    scope.signal_count += 1;
+    let signal_id = scope.signal_count;
+    let idx = signal_id - 1;
+    let signal = Signal {
+        id: (scope_id, signal_id),
+        generation,
+        #[cfg(debug_assertions)]
+        created_at: Location::caller(),
+        _marker: PhantomData,
     };
 
-    for cleanup in cleanups_to_call {
-        cleanup();
+    // If signal already exists (re-render), reuse it
+    if idx < scope.signals.len() {
+        return Ok(signal);
+    }
+
+    let initial_value = match init.into() {
+        SignalInit::Value(v) => v,
+        SignalInit::InitFn(f) => {
+            drop(rt);
+            let val = f();
+            rt = runtime().lock();
+            val
+        }
+    };
+    let scope = rt.ensure_scope(scope_id);
+    scope.signals.push(StoredValue {
+        eq: Some(eq),
+        ..StoredValue::new(Box::new(initial_value))
+    });
+
+    Ok(signal)
+}
+
+/// Create a computed/derived signal that automatically updates based on dependencies
+///
+/// The computation is lazy: it runs the first time the signal is read and
+/// afterwards only when a signal it read has changed. Computed signals that
+/// depend on each other are brought up to date in dependency order, so each
+/// one runs at most once per update and always sees consistent inputs.
+///
+/// # Example
+/// ```rust,no_run
+/// use momenta_core::signals::{create_signal, create_computed};
+///
+/// let count = create_signal(0);
+/// let doubled = create_computed(move || count.get() * 2);
+///
+/// assert_eq!(doubled.get(), 0);
+/// count.set(5);
+/// assert_eq!(doubled.get(), 10);
+/// ```
+#[cfg(any(feature = "computed", feature = "full-reactivity"))]
+#[track_caller]
+pub fn create_computed<T, F>(computation: F) -> Signal<T>
+where
+    T: SignalValue + PartialEq + Clone + 'static,
+    F: Fn() -> T + Send + 'static,
+{
+    create_derived(
+        Box::new(move || Box::new(computation()) as Box<dyn Any + Send>),
+        values_equal::<T>,
+    )
+}
+
+/// Create effect that runs when dependencies change
+///
+/// # Example
+/// ```ignore
+/// use momenta_core::signals::{create_signal, create_effect};
+///
+/// let count = create_signal(0);
+/// create_effect(move || {
+///     println!("Count changed to: {}", count.get());
+/// });
+/// ```
+///
+/// Effects created inside a scope run after the outermost render has patched
+/// the DOM and every [`create_layout_effect`] of the same flush has run.
+#[inline]
+pub fn create_effect(effect: impl Fn() + Send + 'static) {
+    push_effect(Box::new(effect), EffectPhase::Passive);
+}
+
+/// Create an effect that runs right after the scope function, before the
+/// scope's DOM patch is applied.
+///
+/// Child scopes finish rendering inside their parent, so their render effects
+/// run before the parent's. Use it to prepare state the patch depends on; the
+/// DOM still shows the previous render.
+///
+/// # Example
+/// ```ignore
+/// use momenta_core::signals::{create_signal, create_render_effect};
+///
+/// let count = create_signal(0);
+/// create_render_effect(move || {
+///     println!("About to patch count {}", count.get());
+/// });
+/// ```
+#[inline]
+pub fn create_render_effect(effect: impl Fn() + Send + 'static) {
+    push_effect(Box::new(effect), EffectPhase::Render);
+}
+
+/// Create an effect that runs once the outermost render has patched the DOM,
+/// before any [`create_effect`] of the same flush and before the next frame.
+///
+/// Layout effects of child scopes run before those of their parents, so a
+/// parent can measure children that have already laid themselves out. Use it
+/// to measure elements or move focus. Layout effects don't run for transient
+/// scopes such as server rendering.
+///
+/// # Example
+/// ```ignore
+/// use momenta_core::signals::{create_signal, create_layout_effect};
+///
+/// let open = create_signal(false);
+/// create_layout_effect(move || {
+///     if open.get() {
+///         // focus the input rendered for `open`
+///     }
+/// });
+/// ```
+#[inline]
+pub fn create_layout_effect(effect: impl Fn() + Send + 'static) {
+    push_effect(Box::new(effect), EffectPhase::Layout);
+}
+
+fn push_effect(effect: ScopeEffect, phase: EffectPhase) {
+    let mut rt = runtime().lock();
+    let scope_id = rt.owner_scope();
+    let scope = rt.ensure_scope(scope_id);
+    scope.effect_count += 1;
+    scope.effects.push(Some(effect));
+    scope.effect_phases.push(phase);
+    scope.effect_cleanups.push(None);
+    scope.effect_executing.push(false);
+}
+
+/// Create effect with cleanup function
+///
+/// The cleanup function is called when the scope is destroyed or before the effect re-runs.
+///
+/// # Example
+/// ```ignore
+/// use momenta_core::signals::{create_signal, create_effect_with_cleanup};
+///
+/// let count = create_signal(0);
+/// create_effect_with_cleanup(
+///     move || {
+///         println!("Setting up for count: {}", count.get());
+///         // Return cleanup function
+///         move || {
+///             println!("Cleaning up");
+///         }
+///     }
+/// );
+/// ```
+pub fn create_effect_with_cleanup<C>(effect: impl Fn() -> C + Send + 'static)
+where
+    C: FnOnce() + Send + 'static,
+{
+    let (scope_id, effect_idx) = {
+        let mut rt = runtime().lock();
+        let scope_id = rt.owner_scope();
+        let scope = rt.ensure_scope(scope_id);
+        scope.effect_count += 1;
+        let idx = scope.effect_count - 1;
+        (scope_id, idx)
+    };
+
+    let mut rt = runtime().lock();
+    let scope = rt.ensure_scope(scope_id);
+    scope.effects.push(Some(Box::new(move || {
+        {
+            let mut rt = runtime().lock();
+            if let Some(scope) = rt.scope_mut(scope_id) {
+                if let Some(cleanup) = scope
+                    .effect_cleanups
+                    .get_mut(effect_idx)
+                    .and_then(|c| c.take())
+                {
+                    drop(rt);
+                    cleanup();
+                }
+            }
+        }
+
+        let new_cleanup = effect();
+        {
+            let mut rt = runtime().lock();
+            if let Some(scope) = rt.scope_mut(scope_id) {
+                if effect_idx < scope.effect_cleanups.len() {
+                    scope.effect_cleanups[effect_idx] = Some(Box::new(new_cleanup));
+                }
+            }
+        }
+    })));
+    let scope = rt.ensure_scope(scope_id);
+    scope.effect_phases.push(EffectPhase::Passive);
+    scope.effect_cleanups.push(None);
+    scope.effect_executing.push(false);
+}
+
+/// Create an effect that runs async work.
+///
+/// The signals read before the first `.await` are tracked like in
+/// [`create_effect`]. When one of them changes, or the scope is disposed, the
+/// run that is still in flight is aborted: it is dropped at its next `.await`
+/// and never resumes. The work after the first `.await` is handed to the same
+/// spawner as resource fetches (see [`set_spawner`]); without one, it is
+/// dropped.
+///
+/// # Example
+/// ```ignore
+/// use momenta_core::signals::{create_async_effect, create_signal};
+///
+/// let query = create_signal(String::new());
+/// create_async_effect(async move || {
+///     let query = query.get(); // tracked
+///     let results = search(&query).await;
+///     // Only reached if `query` did not change in the meantime.
+///     show(results);
+/// });
+/// ```
+pub fn create_async_effect<F>(effect: F)
+where
+    F: AsyncFn() + Send + Clone + 'static,
+{
+    create_effect_with_cleanup(move || {
+        let effect = effect.clone();
+        let (mut run, handle) = Abortable::new(Box::pin(async move { effect().await }));
+
+        // Poll once right here so the reads before the first `.await` happen
+        // while the effect is tracked. The spawned task polls again, which
+        // registers its real waker.
+        let mut cx = Context::from_waker(Waker::noop());
+        if Pin::new(&mut run).poll(&mut cx).is_pending() && has_spawner() {
+            spawn(run);
+        }
+
+        move || handle.abort()
+    });
+}
+
+/// Shared between an abortable future and the handle that aborts it.
+#[derive(Default)]
+struct AbortState {
+    aborted: bool,
+    waker: Option<Waker>,
+}
+
+/// A future that completes without polling `future` again once aborted.
+struct Abortable {
+    state: Arc<Mutex<AbortState>>,
+    future: LocalFuture,
+}
+
+impl Abortable {
+    fn new(future: LocalFuture) -> (Self, AbortHandle) {
+        let state = Arc::new(Mutex::new(AbortState::default()));
+        let handle = AbortHandle(state.clone());
+        (Abortable { state, future }, handle)
+    }
+}
+
+/// Aborts the [`Abortable`] it was created with.
+#[derive(Clone)]
+struct AbortHandle(Arc<Mutex<AbortState>>);
+
+impl AbortHandle {
+    fn abort(&self) {
+        let waker = {
+            let mut state = self.0.lock();
+            state.aborted = true;
+            state.waker.take()
+        };
+        // Wake the task so the executor drops the aborted future promptly.
+        if let Some(waker) = waker {
+            waker.wake();
+        }
+    }
+}
+
+impl PartialEq for AbortHandle {
+    fn eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.0, &other.0)
+    }
+}
+
+impl core::fmt::Debug for AbortHandle {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_tuple("AbortHandle").finish_non_exhaustive()
+    }
+}
+
+impl Future for Abortable {
+    type Output = ();
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<()> {
+        {
+            let mut state = self.state.lock();
+            if state.aborted {
+                return Poll::Ready(());
+            }
+            state.waker = Some(cx.waker().clone());
+        }
+        self.future.as_mut().poll(cx)
+    }
+}
+
+/// Publish a value to the current scope and every scope created beneath it.
+///
+/// Descendants read it back with [`use_context`]. Providing a second value of
+/// the same type from the same scope replaces the first one, and the value is
+/// dropped together with the providing scope.
+///
+/// # Example
+/// ```ignore
+/// use momenta_core::signals::{create_signal, provide_context, use_context};
+///
+/// #[derive(Clone, Copy)]
+/// struct Theme(Signal<&'static str>);
+///
+/// provide_context(Theme(create_signal("dark")));
+///
+/// // Later, in any descendant component:
+/// let theme = use_context::<Theme>().expect("Theme was not provided");
+/// ```
+pub fn provide_context<T: Clone + Send + 'static>(value: T) {
+    let mut rt = runtime().lock();
+    let scope_id = rt.owner_scope();
+    rt.ensure_scope(scope_id)
+        .contexts
+        .insert(TypeId::of::<T>(), Box::new(value));
+}
+
+/// Look up the nearest value of type `T` provided by the current scope or one
+/// of its ancestors.
+///
+/// Returns `None` outside a scope or when no ancestor provided a `T`.
+pub fn use_context<T: Clone + 'static>() -> Option<T> {
+    let rt = runtime().lock();
+    let mut scope_id = rt.current_scope;
+    while let Some(id) = scope_id {
+        let scope = rt.scope(id)?;
+        if let Some(value) = scope.contexts.get(&TypeId::of::<T>()) {
+            return value.downcast_ref::<T>().cloned();
+        }
+        scope_id = scope.parent;
+    }
+    None
+}
+
+pub fn run_scope(
+    scope_fn: impl FnMut() -> Node + Send + 'static,
+    callback: impl Fn(&Node) + Send + Sync + 'static,
+) -> Node {
+    let scope_id = {
+        let mut rt = runtime().lock();
+        let id = rt.alloc_scope_id();
+        let parent = rt.current_scope;
+        let scope = rt.ensure_scope(id);
+        scope.function = Some(Box::new(scope_fn));
+        scope.callback = Some(Arc::new(callback));
+        scope.parent = parent;
+        if let Some(parent) = parent.and_then(|p| rt.scope_mut(p)) {
+            parent.children.push(id);
+        }
+        id
+    };
+
+    render_scope(scope_id)
+}
+
+/// Run a scope once and dispose all reactive state created for it afterwards.
+///
+/// Optimized path for transient scopes: avoids storing the scope function and
+/// callback in the runtime since they won't be needed for re-renders, and
+/// skips re-entrancy checks and signal change detection. Nothing is patched
+/// afterwards, so layout effects are skipped; passive effects still run, and
+/// their cleanups run right after them, when the scope is torn down.
+#[inline]
+pub fn run_scope_transient(
+    scope_fn: impl FnOnce() -> Node + Send + 'static,
+    callback: impl Fn(&Node) + Send + Sync + 'static,
+) -> Node {
+    let (scope_id, previous_scope, was_transient) = {
+        let mut rt = runtime().lock();
+        let id = rt.alloc_scope_id();
+        let prev = rt.current_scope;
+        rt.ensure_scope(id).parent = prev;
+        let was_transient = rt.transient_scope;
+        rt.current_scope = Some(id);
+        rt.transient_scope = true;
+        runtime().in_scope.store(true, AtomicOrdering::Relaxed);
+        (id, prev, was_transient)
+    };
+
+    let node = scope_fn();
+
+    run_scope_effects(scope_id, EffectPhase::Render);
+    callback(&node);
+
+    // Single-lock fast path when there are no effects or child scopes (common case).
+    let (effects, children): (Vec<ScopeEffect>, Vec<usize>) = {
+        let mut rt = runtime().lock();
+        let needs_teardown = rt
+            .scope(scope_id)
+            .map(|s| s.effect_count > 0 || !s.children.is_empty())
+            .unwrap_or(false);
+        if needs_teardown {
+            rt.executing_effects_count += 1;
+            let scope = rt.scope_mut(scope_id).unwrap();
+            let children = core::mem::take(&mut scope.children);
+            let effects: Vec<_> = scope
+                .effects
+                .iter_mut()
+                .zip(&scope.effect_phases)
+                .filter(|(_, phase)| **phase == EffectPhase::Passive)
+                .filter_map(|(e, _)| e.take())
+                .collect();
+            (effects, children)
+        } else {
+            // No effects: do full teardown in this single lock acquisition
+            rt.current_scope = previous_scope;
+            rt.transient_scope = was_transient;
+            runtime()
+                .in_scope
+                .store(previous_scope.is_some(), AtomicOrdering::Relaxed);
+            rt.release_scope_signals(scope_id);
+            if let Some(scope) = rt.scope_mut(scope_id) {
+                scope.clear();
+            }
+            rt.scope_signal_changes.clear();
+            rt.release_scope_id(scope_id);
+            drop(rt);
+            wake_streams();
+            return node;
+        }
+    };
+
+    for effect in &effects {
+        effect();
+    }
+
+    for child in children {
+        dispose_scope(child);
+    }
+    // Runs the cleanups the effects just registered, e.g. aborting the work
+    // an async effect left in flight.
+    clear_scope_effects(scope_id);
+
+    {
+        let mut rt = runtime().lock();
+        rt.executing_effects_count -= 1;
+        rt.current_scope = previous_scope;
+        rt.transient_scope = was_transient;
+        runtime()
+            .in_scope
+            .store(previous_scope.is_some(), AtomicOrdering::Relaxed);
+        rt.release_scope_signals(scope_id);
+        if let Some(scope) = rt.scope_mut(scope_id) {
+            scope.clear();
+        }
+
+        rt.scope_signal_changes.clear();
+
+        rt.release_scope_id(scope_id);
+    }
+    wake_streams();
+
+    node
+}
+
+/// Runs a closure inside a fresh [`Runtime`] that is dropped afterwards.
+pub fn with_isolated_runtime<R>(f: impl FnOnce() -> R) -> R {
+    Runtime::new().enter(f)
+}
+
+#[allow(dead_code)]
+pub(crate) fn get_current_scope() -> Option<usize> {
+    runtime().lock().current_scope
+}
+
+/// Returns true when code is currently executing inside a reactive scope.
+#[inline]
+pub fn has_current_scope() -> bool {
+    runtime().in_scope.load(AtomicOrdering::Relaxed)
+}
+
+fn clear_scope_effects(scope_id: usize) {
+    let cleanups_to_call = {
+        let mut rt = runtime().lock();
+        let mut cleanups = Vec::new();
+        if let Some(scope) = rt.scope_mut(scope_id) {
+            for eff in scope.effects.iter_mut() {
+                *eff = None;
+            }
+            for cleanup in scope.effect_cleanups.iter_mut() {
+                if let Some(c) = cleanup.take() {
+                    cleanups.push(c);
+                }
+            }
+        }
+        cleanups
+    };
+
+    for cleanup in cleanups_to_call {
+        cleanup();
+    }
+}
+
+/// Dispose a scope and all of its descendants, running effect cleanups from
+/// the leaves upwards and releasing every signal, dependency edge and id the
+/// scopes owned.
+fn dispose_scope(scope_id: usize) {
+    let children = {
+        let mut rt = runtime().lock();
+        match rt.scope_mut(scope_id) {
+            Some(scope) => core::mem::take(&mut scope.children),
+            None => return,
+        }
+    };
+    for child in children {
+        dispose_scope(child);
+    }
+
+    clear_scope_effects(scope_id);
+
+    // Dropped after the lock is released so values owned by the scope may
+    // safely touch the runtime from their destructors.
+    let disposed = {
+        let mut rt = runtime().lock();
+        rt.release_scope_signals(scope_id);
+        let Some(scope) = rt.scopes.get_mut(scope_id - 1).and_then(Option::take) else {
+            return;
+        };
+
+        if let Some(parent) = scope.parent.and_then(|p| rt.scope_mut(p)) {
+            parent.children.retain(|c| *c != scope_id);
+        }
+
+        rt.pending_scope_renders.retain(|s| *s != scope_id);
+        rt.scope_signal_changes.retain(|(s, _)| *s != scope_id);
+
+        if let Some(signal_ids) = rt.scope_dependencies.remove(&scope_id) {
+            for signal_id in signal_ids {
+                if let Some(scopes) = rt.signal_dependencies.get_mut(&signal_id) {
+                    scopes.retain(|s| *s != scope_id);
+                }
+            }
+        }
+        rt.untrack_scope_keys(scope_id);
+
+        let owned_signals: Vec<_> = rt
+            .signal_dependencies
+            .range((scope_id, 0)..(scope_id + 1, 0))
+            .map(|(id, _)| *id)
+            .collect();
+        for signal_id in owned_signals {
+            for dependent in rt
+                .signal_dependencies
+                .remove(&signal_id)
+                .unwrap_or_default()
+            {
+                if let Some(deps) = rt.scope_dependencies.get_mut(&dependent) {
+                    deps.retain(|id| *id != signal_id);
+                }
+            }
+        }
+        let owned_keyed: Vec<_> = rt
+            .keyed_dependencies
+            .range((scope_id, 0)..(scope_id + 1, 0))
+            .map(|(id, _)| *id)
+            .collect();
+        for signal_id in owned_keyed {
+            rt.keyed_dependencies.remove(&signal_id);
+        }
+
+        rt.release_scope_id(scope_id);
+        scope
+    };
+    wake_streams();
+
+    if disposed.keeps_children
+        && let Some(patcher) = keyed_list::installed_list_patcher()
+    {
+        patcher.dispose_list(&keyed_list::list_marker(scope_id));
+    }
+}
+
+/// A handle to a reactive scope that can be used to tear it down explicitly,
+/// e.g. when a component is unmounted.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ScopeHandle {
+    id: usize,
+    generation: u32,
+}
+
+impl ScopeHandle {
+    /// Returns a handle to the scope that is currently rendering, if any.
+    pub fn current() -> Option<Self> {
+        let rt = runtime().lock();
+        rt.current_scope.map(|id| Self {
+            id,
+            generation: rt.scope_generation(id),
+        })
+    }
+
+    /// Returns true until the scope has been disposed.
+    pub fn is_alive(&self) -> bool {
+        let rt = runtime().lock();
+        rt.scope_generation(self.id) == self.generation && rt.scope(self.id).is_some()
+    }
+
+    /// Dispose the scope together with all of its descendants, running their
+    /// effect cleanups. Disposing an already disposed scope does nothing.
+    pub fn dispose(self) {
+        if self.is_alive() {
+            dispose_scope(self.id);
+        }
+    }
+}
+
+struct ScopeGuard {
+    previous_scope: Option<usize>,
+    previous_untracked: bool,
+}
+
+/// Closes a `render_scope` call, even when the scope function panics.
+struct RenderGuard;
+
+impl Drop for RenderGuard {
+    fn drop(&mut self) {
+        let mut rt = runtime().lock();
+        rt.rendering_scope = 0;
+        rt.render_depth -= 1;
+    }
+}
+
+impl Drop for ScopeGuard {
+    fn drop(&mut self) {
+        let mut rt = runtime().lock();
+        rt.current_scope = self.previous_scope;
+        rt.untracked = self.previous_untracked;
+        drop(rt);
+        runtime()
+            .in_scope
+            .store(self.previous_scope.is_some(), AtomicOrdering::Relaxed);
+    }
+}
+
+fn render_scope(scope_id: usize) -> Node {
+    let children = {
+        let mut rt = runtime().lock();
+
+        if rt.rendering_scope == scope_id {
+            rt.push_pending_render(scope_id);
+            return Node::Empty;
+        }
+
+        let Some(scope) = rt.scope_mut(scope_id) else {
+            return Node::Empty;
+        };
+        let children = if scope.keeps_children {
+            Vec::new()
+        } else {
+            core::mem::take(&mut scope.children)
+        };
+
+        let has_dependencies = rt.scope_dependencies.contains_key(&scope_id);
+        if has_dependencies {
+            let has_changes = rt
+                .signal_dependencies
+                .iter()
+                .any(|(_, scopes)| scopes.contains(&scope_id));
+            if !has_changes {
+                if let Some(scope) = rt.scope_mut(scope_id) {
+                    scope.children.extend(children);
+                }
+                return Node::Empty;
+            }
+        }
+
+        children
+    };
+
+    // Everything the previous render created is torn down before rendering again.
+    for child in children {
+        dispose_scope(child);
+    }
+    clear_scope_effects(scope_id);
+
+    let (previous_scope, previous_untracked, scope_fn) = {
+        let mut rt = runtime().lock();
+
+        let previous_scope = rt.current_scope;
+        rt.current_scope = Some(scope_id);
+        let previous_untracked = core::mem::replace(&mut rt.untracked, false);
+        runtime().in_scope.store(true, AtomicOrdering::Relaxed);
+
+        rt.rendering_scope = scope_id;
+        rt.render_depth += 1;
+        rt.scope_signal_changes
+            .retain(|&(scope, _)| scope != scope_id);
+
+        if let Some(signal_ids) = rt.scope_dependencies.remove(&scope_id) {
+            for signal_id in signal_ids {
+                if let Some(scopes) = rt.signal_dependencies.get_mut(&signal_id) {
+                    scopes.retain(|s| *s != scope_id);
+                }
+            }
+        }
+        rt.untrack_scope_keys(scope_id);
+
+        let scope_fn = rt.scope_mut(scope_id).and_then(|s| s.function.take());
+
+        if let Some(scope) = rt.scope_mut(scope_id) {
+            scope.effects.clear();
+            scope.effect_cleanups.clear();
+            scope.effect_executing.clear();
+            scope.effect_phases.clear();
+            scope.effect_count = 0;
+            #[cfg(any(feature = "devtools", test))]
+            {
+                scope.render_count += 1;
+                scope.effect_reads.clear();
+            }
+        }
+
+        (previous_scope, previous_untracked, scope_fn)
+    }; // Lock released here before calling user code
+
+    let render = RenderGuard;
+    let _guard = ScopeGuard {
+        previous_scope,
+        previous_untracked,
+    };
+
+    let node = scope_fn.map(|mut fnc| {
+        let mut node = fnc();
+        run_scope_effects(scope_id, EffectPhase::Render);
+
+        let callback = {
+            let mut rt = runtime().lock();
+            let scope = rt.ensure_scope(scope_id);
+            scope.function = Some(fnc);
+            if let Some(el) = node.as_element_mut() {
+                el.key = scope_id.to_string();
+            }
+            scope.callback.clone()
+        };
+
+        if let Some(callback) = callback {
+            callback(&node);
+        }
+
+        node
+    });
+
+    drop(render);
+    let run_queued = {
+        let mut rt = runtime().lock();
+        let has_effects = match rt.scope_mut(scope_id) {
+            Some(scope) => {
+                scope.signal_count = 0;
+                scope.effect_count = 0;
+                scope
+                    .effect_phases
+                    .iter()
+                    .any(|p| *p != EffectPhase::Render)
+            }
+            None => false,
+        };
+        let queued = (scope_id, rt.scope_generation(scope_id));
+        if has_effects && !rt.queued_effects.contains(&queued) {
+            rt.queued_effects.push(queued);
+        }
+        queue_changed_dependents(&mut rt, scope_id);
+        rt.render_depth == 0
+    };
+
+    if run_queued {
+        drop(_guard);
+        run_queued_effects();
+    }
+
+    node.unwrap_or(Node::Empty)
+}
+
+/// Queue the scopes that depend on signals changed during a render or its
+/// effects, except `scope_id` itself.
+fn queue_changed_dependents(rt: &mut RuntimeState, scope_id: usize) {
+    if rt.scope_signal_changes.is_empty() {
+        return;
+    }
+    let changed = rt.scope_signal_changes.to_vec();
+    let mut scopes_to_queue = Vec::new();
+    for signal_id in changed {
+        if let Some(dependent_scopes) = rt.signal_dependencies.get(&signal_id) {
+            for &dep_scope in dependent_scopes {
+                if dep_scope != scope_id {
+                    scopes_to_queue.push(dep_scope);
+                }
+            }
+        }
+    }
+    for dep_scope in scopes_to_queue {
+        rt.push_pending_render(dep_scope);
+    }
+}
+
+/// Run the layout effects of every scope the outermost render queued, then
+/// their passive effects, each in the order the scopes finished rendering.
+/// Scopes disposed in the meantime are skipped, even if their id was reused.
+fn run_queued_effects() {
+    let scopes = core::mem::take(&mut runtime().lock().queued_effects);
+    for phase in [EffectPhase::Layout, EffectPhase::Passive] {
+        for &(scope_id, generation) in &scopes {
+            let _guard = {
+                let mut rt = runtime().lock();
+                if rt.scope_generation(scope_id) != generation || rt.scope(scope_id).is_none() {
+                    continue;
+                }
+                let previous_scope = rt.current_scope.replace(scope_id);
+                let previous_untracked = core::mem::replace(&mut rt.untracked, false);
+                runtime().in_scope.store(true, AtomicOrdering::Relaxed);
+                ScopeGuard {
+                    previous_scope,
+                    previous_untracked,
+                }
+            };
+            run_scope_effects(scope_id, phase);
+            queue_changed_dependents(&mut runtime().lock(), scope_id);
+        }
+    }
+}
+
+fn run_scope_effects(scope_id: usize, phase: EffectPhase) {
+    let effect_count = {
+        let rt = runtime().lock();
+        rt.scope(scope_id).map(|s| s.effects.len()).unwrap_or(0)
+    };
+
+    if effect_count == 0 {
+        return;
+    }
+
+    for idx in 0..effect_count {
+        {
+            let mut rt = runtime().lock();
+            let Some(scope) = rt.scope(scope_id) else {
+                return;
+            };
+            if scope.effect_phases.get(idx) != Some(&phase)
+                || scope.effect_executing.get(idx).copied().unwrap_or(false)
+            {
+                continue;
+            }
+            if let Some(scope) = rt.scope_mut(scope_id) {
+                if idx < scope.effect_executing.len() {
+                    scope.effect_executing[idx] = true;
+                }
+            }
+            rt.executing_effects_count += 1;
+        }
+
+        let effect_fn = {
+            let mut rt = runtime().lock();
+            #[cfg(any(feature = "devtools", test))]
+            {
+                rt.running_effect = Some((scope_id, idx));
+                if let Some(reads) = rt
+                    .scope_mut(scope_id)
+                    .and_then(|s| s.effect_reads.get_mut(idx))
+                {
+                    reads.clear();
+                }
+            }
+            rt.scope_mut(scope_id)
+                .and_then(|s| s.effects.get_mut(idx).and_then(|e| e.take()))
+        };
+        if let Some(effect) = &effect_fn {
+            effect();
+        }
+        #[cfg(any(feature = "devtools", test))]
+        {
+            runtime().lock().running_effect = None;
+        }
+        if let Some(effect) = effect_fn {
+            let mut rt = runtime().lock();
+            if let Some(scope) = rt.scope_mut(scope_id) {
+                if idx < scope.effects.len() {
+                    scope.effects[idx] = Some(effect);
+                }
+            }
+        }
+
+        {
+            let mut rt = runtime().lock();
+            if let Some(scope) = rt.scope_mut(scope_id) {
+                if idx < scope.effect_executing.len() {
+                    scope.effect_executing[idx] = false;
+                }
+            }
+            rt.executing_effects_count -= 1;
+        }
+    }
+}
+
+fn process_pending_renders() {
+    let limit = MAX_RENDER_ITERATIONS.load(AtomicOrdering::Relaxed);
+    let mut renders = BTreeMap::<usize, usize>::new();
+    let mut iterations = 0;
+
+    while let Some(scope_id) = {
+        flush_stale_nodes();
+        let mut rt = runtime().lock();
+        if rt.pending_scope_renders.is_empty() {
+            None
+        } else {
+            // Render the shallowest scope first: re-rendering a parent disposes
+            // its children, which drops their pending renders as well.
+            let min_idx = rt
+                .pending_scope_renders
+                .iter()
+                .enumerate()
+                .min_by_key(|&(_, &id)| (rt.scope_depth(id), id))
+                .map(|(i, _)| i)
+                .unwrap();
+            Some(rt.pending_scope_renders.swap_remove(min_idx))
+        }
+    } {
+        if iterations == limit {
+            report_render_loop(iterations, &renders);
+            break;
+        }
+        iterations += 1;
+        *renders.entry(scope_id).or_default() += 1;
+        render_scope(scope_id);
+    }
+}
+
+/// Drop the pending renders of a flush that hit the render limit and report
+/// the scopes that kept re-rendering together with the signals they read.
+#[cold]
+fn report_render_loop(iterations: usize, renders: &BTreeMap<usize, usize>) {
+    let error = {
+        let mut rt = runtime().lock();
+        rt.pending_scope_renders.clear();
+
+        let scopes: Vec<usize> = renders
+            .iter()
+            .filter(|&(_, &count)| count > 1)
+            .map(|(&id, _)| id)
+            .collect();
+        let mut signals: Vec<(usize, usize)> = scopes
+            .iter()
+            .flat_map(|id| {
+                let plain = rt.scope_dependencies.get(id).into_iter().flatten();
+                let keyed = rt.scope_keyed_dependencies.get(id).into_iter().flatten();
+                plain.chain(keyed).copied()
+            })
+            .collect();
+        signals.sort_unstable();
+        signals.dedup();
+
+        ReactiveError::RenderLoop {
+            iterations,
+            scopes,
+            signals,
+        }
+    };
+    report_error(&error);
+}
+
+/// An error detected by the reactive runtime, passed to the hook installed
+/// with [`set_error_hook`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum ReactiveError {
+    /// Renders kept triggering each other, usually because an effect sets a
+    /// signal that (indirectly) causes its own scope to render again. The
+    /// remaining renders were dropped.
+    RenderLoop {
+        /// Renders performed before giving up.
+        iterations: usize,
+        /// Scopes that rendered more than once.
+        scopes: Vec<usize>,
+        /// Signals those scopes read, by owning scope and slot.
+        signals: Vec<(usize, usize)>,
+    },
+    /// Computed or memoized values kept going stale while they were brought
+    /// up to date, usually because a computation writes to a signal it
+    /// (indirectly) reads. They are left stale until they are read again.
+    FlushLoop {
+        /// Rounds performed before giving up.
+        iterations: usize,
+        /// The values that were still stale, by owning scope and slot.
+        signals: Vec<(usize, usize)>,
+    },
+    /// A failed transaction could not restore some signals, because they
+    /// were updated in place without keeping a copy of the old value, e.g.
+    /// with [`Signal::update`]. They keep their new values.
+    PartialRollback {
+        /// The signals that were not restored, by owning scope and slot.
+        signals: Vec<(usize, usize)>,
+    },
+}
+
+impl core::fmt::Display for ReactiveError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            ReactiveError::RenderLoop {
+                iterations,
+                scopes,
+                signals,
+            } => write!(
+                f,
+                "render loop detected: stopped after {iterations} renders \
+                 (scopes {scopes:?}, signals {signals:?})"
+            ),
+            ReactiveError::FlushLoop {
+                iterations,
+                signals,
+            } => write!(
+                f,
+                "derived values kept changing: stopped after {iterations} rounds \
+                 (signals {signals:?})"
+            ),
+            ReactiveError::PartialRollback { signals } => write!(
+                f,
+                "transaction rolled back without restoring signals {signals:?}, \
+                 which were updated in place"
+            ),
+        }
+    }
+}
+
+/// Install the function that receives runtime errors such as render loops.
+///
+/// Without a hook, errors are logged to the console on wasm. Elsewhere they
+/// panic in debug builds, so loops surface in tests, and are printed to
+/// stderr in release builds with `std` (dropped without it), so a server
+/// keeps serving.
+///
+/// The hook is process-global: it receives the errors of every [`Runtime`].
+///
+/// # Example
+/// ```ignore
+/// use momenta_core::signals::set_error_hook;
+///
+/// set_error_hook(|error| log::error!("{error}"));
+/// ```
+pub fn set_error_hook(hook: impl Fn(&ReactiveError) + Send + Sync + 'static) {
+    *ERROR_HOOK.lock() = Some(Arc::new(hook));
+}
+
+/// Set how many scope renders one update may cause before it is treated as
+/// a render loop. Defaults to 100.
+pub fn set_max_render_iterations(limit: usize) {
+    MAX_RENDER_ITERATIONS.store(limit, AtomicOrdering::Relaxed);
+}
+
+fn report_error(error: &ReactiveError) {
+    let hook = ERROR_HOOK.lock().clone();
+    if let Some(hook) = hook {
+        return hook(error);
+    }
+    #[cfg(all(feature = "wasm", target_arch = "wasm32"))]
+    web_sys::console::error_1(&error.to_string().into());
+    #[cfg(all(debug_assertions, not(all(feature = "wasm", target_arch = "wasm32"))))]
+    panic!("{error}");
+    #[cfg(all(
+        not(debug_assertions),
+        feature = "std",
+        not(all(feature = "wasm", target_arch = "wasm32"))
+    ))]
+    std::eprintln!("momenta: {error}");
+}
+
+/// Serializes tests that use the global runtime or the process-global hooks.
+#[cfg(test)]
+pub(crate) static TEST_MUTEX: Mutex<()> = Mutex::new(());
+
+#[cfg(test)]
+fn reset_runtime_state() {
+    *GLOBAL_RUNTIME.state.lock() = RuntimeState::new();
+    GLOBAL_RUNTIME
+        .in_scope
+        .store(false, AtomicOrdering::Relaxed);
+}
+
+/// Run `f` as the function of a new root scope and return what its first run
+/// returned, with a handle to the scope. `f` runs again whenever the scope
+/// re-renders.
+#[cfg(test)]
+pub(crate) fn in_root_scope<R: Send + 'static>(
+    mut f: impl FnMut() -> R + Send + 'static,
+) -> (R, ScopeHandle) {
+    let slot = Arc::new(Mutex::new(None));
+    let mut captured = Some(slot.clone());
+    run_scope(
+        move || {
+            let result = f();
+            if let Some(captured) = captured.take() {
+                let scope = ScopeHandle::current().expect("rendering a scope");
+                *captured.lock() = Some((result, scope));
+            }
+            Node::Empty
+        },
+        |_| {},
+    );
+    slot.lock().take().expect("the scope function ran")
+}
+
+pub(crate) static SPAWNER: Mutex<Option<Spawner>> = Mutex::new(None);
+
+/// Install the function that runs async work such as resource fetches.
+///
+/// On wasm, futures go to `wasm_bindgen_futures::spawn_local` until a spawner
+/// is set. Everywhere else nothing is spawned without one, so resources stay
+/// idle; the `futures-executor` and `tokio` features provide ready-made
+/// spawners in `momenta_core::executor`.
+///
+/// Spawned futures are always polled inside the [`Runtime`] that was current
+/// when they were created, whichever thread or task ends up polling them.
+/// The spawner itself is process-global and shared by every runtime.
+///
+/// # Example
+/// ```ignore
+/// use momenta_core::signals::set_spawner;
+///
+/// set_spawner(|future| {
+///     my_executor::spawn_local(future);
+/// });
+/// ```
+pub fn set_spawner(spawner: impl Fn(LocalFuture) + Send + Sync + 'static) {
+    *SPAWNER.lock() = Some(Arc::new(spawner));
+}
+
+/// Returns true when [`spawn`] has somewhere to send futures.
+fn has_spawner() -> bool {
+    #[cfg(feature = "std")]
+    if TASK_COLLECTOR.with(|collector| collector.borrow().is_some()) {
+        return true;
+    }
+    cfg!(all(feature = "wasm", target_arch = "wasm32")) || SPAWNER.lock().is_some()
+}
+
+/// Hand a future to the installed spawner, bound to the current runtime.
+fn spawn(future: impl Future<Output = ()> + 'static) {
+    let future: LocalFuture = Box::pin(InRuntime {
+        runtime: Runtime::current(),
+        future: Box::pin(future),
+    });
+
+    #[cfg(feature = "std")]
+    let Some(future) = TASK_COLLECTOR.with(|collector| match collector.borrow_mut().as_mut() {
+        Some(tasks) => {
+            tasks.push(future);
+            None
+        }
+        None => Some(future),
+    }) else {
+        return;
+    };
+
+    let spawner = SPAWNER.lock().clone();
+    match spawner {
+        Some(spawner) => spawner(future),
+        #[cfg(all(feature = "wasm", target_arch = "wasm32"))]
+        None => wasm_bindgen_futures::spawn_local(future),
+        #[cfg(not(all(feature = "wasm", target_arch = "wasm32")))]
+        None => drop(future),
+    }
+}
+
+/// Polls a future inside the runtime it was spawned from.
+struct InRuntime {
+    runtime: Option<Runtime>,
+    future: LocalFuture,
+}
+
+impl Future for InRuntime {
+    type Output = ();
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<()> {
+        let this = &mut *self;
+        let ptr = this
+            .runtime
+            .as_ref()
+            .map_or(core::ptr::null(), |runtime| Arc::as_ptr(&runtime.cell));
+        match enter_runtime_ptr(ptr, || this.future.as_mut().poll(cx)) {
+            Ok(poll) => poll,
+            Err(err) => panic!("{err}"),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+std::thread_local! {
+    /// Set while a [`TaskSet`] is collecting on this thread.
+    static TASK_COLLECTOR: core::cell::RefCell<Option<Vec<LocalFuture>>> =
+        const { core::cell::RefCell::new(None) };
+}
+
+/// Futures spawned by reactive code that the caller drives itself instead of
+/// handing them to the spawner.
+///
+/// Server-side rendering uses this to wait for resources: the render runs
+/// inside [`TaskSet::collect`] and the returned set is awaited, which polls
+/// every collected future, and every future those spawn in turn, to
+/// completion. The set is not `Send`; await it on the thread that created it.
+///
+/// # Example
+/// ```ignore
+/// use momenta_core::signals::{Runtime, TaskSet};
+///
+/// let runtime = Runtime::new();
+/// let (_, tasks) = TaskSet::collect(|| runtime.enter(|| render_app()));
+/// tasks.await; // every resource created by the render has settled
+/// ```
+#[cfg(feature = "std")]
+pub struct TaskSet {
+    tasks: Vec<LocalFuture>,
+}
+
+#[cfg(feature = "std")]
+impl TaskSet {
+    /// Run `f`, keeping back every future it spawns.
+    pub fn collect<R>(f: impl FnOnce() -> R) -> (R, Self) {
+        let previous = TASK_COLLECTOR.with(|collector| collector.borrow_mut().replace(Vec::new()));
+        let result = f();
+        let tasks = TASK_COLLECTOR
+            .with(|collector| core::mem::replace(&mut *collector.borrow_mut(), previous))
+            .unwrap_or_default();
+        (result, Self { tasks })
+    }
+
+    pub fn len(&self) -> usize {
+        self.tasks.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.tasks.is_empty()
+    }
+}
+
+#[cfg(feature = "std")]
+impl Future for TaskSet {
+    type Output = ();
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<()> {
+        let mut tasks = core::mem::take(&mut self.tasks);
+        loop {
+            let cx = &mut *cx;
+            let (pending, spawned) = Self::collect(move || {
+                tasks.retain_mut(|task| task.as_mut().poll(cx).is_pending());
+                tasks
+            });
+            tasks = pending;
+            if spawned.is_empty() {
+                break;
+            }
+            // Newly spawned futures have to be polled once to register a waker.
+            tasks.extend(spawned.tasks);
+        }
+
+        self.tasks = tasks;
+        if self.tasks.is_empty() {
+            Poll::Ready(())
+        } else {
+            Poll::Pending
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ResourceStatus<E = ()> {
+    Idle,
+    Pending,
+    Loading,
+    Resolved,
+    /// The last fetch failed. The previously resolved value, if any, is kept.
+    Errored(E),
+}
+
+/// Bookkeeping for the fetches a resource has started.
+#[derive(Debug, Clone, PartialEq)]
+struct ResourceTracker<S> {
+    /// Bumped for every fetch; only the response of the latest one is kept.
+    version: u64,
+    /// The source value the latest fetch was started with.
+    source: Option<S>,
+    /// Aborts the latest fetch once a newer one supersedes it.
+    in_flight: Option<AbortHandle>,
+}
+
+pub struct Resource<T, E = ()> {
+    status: Signal<ResourceStatus<E>>,
+    value: Signal<Option<T>>,
+}
+
+impl<T, E> Copy for Resource<T, E> {}
+
+impl<T, E> Clone for Resource<T, E> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<T: SignalValue + PartialEq + 'static, E: PartialEq + Send + 'static> Resource<T, E> {
+    pub fn status(&self) -> Signal<ResourceStatus<E>> {
+        self.status
+    }
+
+    pub fn get(&self) -> Option<T>
+    where
+        T: Clone,
+    {
+        self.value.get()
+    }
+
+    pub fn with<R>(&self, f: impl FnOnce(&T) -> R) -> Option<R> {
+        self.value.with(|v| v.as_ref().map(f)).unwrap_or_default()
+    }
+
+    /// Returns the error of the last fetch if it failed.
+    pub fn error(&self) -> Option<E>
+    where
+        E: Clone,
+    {
+        self.status
+            .with(|status| match status {
+                ResourceStatus::Errored(err) => Some(err.clone()),
+                _ => None,
+            })
+            .flatten()
+    }
+
+    pub fn retry(&self) {
+        self.status.set(ResourceStatus::Pending);
+    }
+}
+
+/// Create a resource that can be asynchronously loaded
+///
+/// The resolved value is not sent to the client when the page is rendered
+/// on the server, so a hydrated page fetches it again. Use
+/// `create_serialized_resource` (with the `serde` feature) to reuse the
+/// server's value instead.
+pub fn create_resource<T, F>(fetcher: F) -> Resource<T>
+where
+    T: SignalValue + PartialEq + 'static,
+    F: AsyncFn() -> T + Send + Clone + 'static,
+{
+    create_resource_inner(
+        || (),
+        move |()| {
+            let fetcher = fetcher.clone();
+            async move { Ok(fetcher().await) }
+        },
+    )
+}
+
+/// Create a resource whose resolved value is handed from the server to the
+/// client during hydration.
+///
+/// When rendered on the server, the resolved value is recorded under `key` and
+/// written into the page by `momenta-ssr`. `hydrate_root` restores it on the
+/// client, where the resource then starts out resolved instead of fetching
+/// again. `key` must be unique among the serialized values of a page.
+///
+/// # Example
+/// ```ignore
+/// use momenta_core::signals::create_serialized_resource;
+///
+/// let stories = create_serialized_resource("top-stories", || async {
+///     fetch_top_stories().await
+/// });
+/// ```
+#[cfg(feature = "serde")]
+pub fn create_serialized_resource<T, F>(key: impl Into<String>, fetcher: F) -> Resource<T>
+where
+    T: SignalValue + PartialEq + serde::Serialize + serde::de::DeserializeOwned + 'static,
+    F: AsyncFn() -> T + Send + Clone + 'static,
+{
+    let key = key.into();
+    let restored = runtime()
+        .lock()
+        .restored_state
+        .remove(&key)
+        .and_then(|value| serde_json::from_value::<T>(value).ok());
+
+    let resource = create_resource_inner(
+        || (),
+        move |()| {
+            let fetcher = fetcher.clone();
+            let key = key.clone();
+            async move {
+                let value = fetcher().await;
+                if let Ok(json) = serde_json::to_value(&value) {
+                    runtime().lock().hydration_state.insert(key, json);
+                }
+                Ok(value)
+            }
+        },
+    );
+
+    if let Some(value) = restored {
+        resource.value.set(Some(value));
+        resource.status.set(ResourceStatus::Resolved);
+    }
+
+    resource
+}
+
+/// Create a signal whose value is sent from the server to the client.
+///
+/// On the server, the value the signal holds at the end of the render (or when
+/// its scope is disposed) is recorded under `key` and written to the hydration
+/// state by `momenta-ssr`. On the client, `hydrate_root` restores that state
+/// before the first render, so the signal starts with the server's value
+/// instead of `init`.
+///
+/// # Example
+/// ```ignore
+/// use momenta_core::signals::create_serialized_signal;
+///
+/// let theme = create_serialized_signal("theme", String::from("light"));
+/// ```
+#[cfg(feature = "serde")]
+pub fn create_serialized_signal<T, I>(key: impl Into<String>, init: I) -> Signal<T>
+where
+    T: SignalValue + PartialEq + serde::Serialize + serde::de::DeserializeOwned + 'static,
+    I: Into<SignalInit<T>>,
+{
+    let key = key.into();
+    let restored = runtime()
+        .lock()
+        .restored_state
+        .remove(&key)
+        .and_then(|value| serde_json::from_value::<T>(value).ok());
+    let signal = match restored {
+        Some(value) => create_signal(value),
+        None => create_signal(init),
+    };
+
+    runtime()
+        .lock()
+        .serialized_signals
+        .entry(signal.id)
+        .or_insert_with(|| (key, serialize_value::<T>));
+    signal
+}
+
+#[cfg(feature = "serde")]
+fn serialize_value<T: serde::Serialize + 'static>(value: &dyn Any) -> Option<serde_json::Value> {
+    let value = value.downcast_ref::<T>()?;
+    serde_json::to_value(value).ok()
+}
+
+/// Returns everything recorded by serialized signals and resources in the
+/// current runtime as a JSON object, or `None` if nothing was recorded.
+#[cfg(feature = "serde")]
+pub fn take_hydration_state() -> Option<String> {
+    let state = {
+        let mut rt = runtime().lock();
+        rt.record_serialized_signals(None);
+        core::mem::take(&mut rt.hydration_state)
+    };
+    if state.is_empty() {
+        return None;
+    }
+    serde_json::to_string(&state).ok()
+}
+
+/// Make the values in a JSON object produced by [`take_hydration_state`]
+/// available to the serialized signals and resources created afterwards.
+#[cfg(feature = "serde")]
+pub fn restore_hydration_state(json: &str) -> Result<(), serde_json::Error> {
+    let state: BTreeMap<String, serde_json::Value> = serde_json::from_str(json)?;
+    runtime().lock().restored_state.extend(state);
+    Ok(())
+}
+
+/// Create a resource that is fetched again whenever `source` changes.
+///
+/// The fetcher receives the current source value and returns a `Result`; an
+/// `Err` puts the resource into [`ResourceStatus::Errored`]. When the source
+/// changes while a fetch is still in flight, the older response is discarded
+/// so it can never overwrite the newer one.
+///
+/// # Example
+/// ```ignore
+/// use momenta_core::signals::{create_resource_with_source, create_signal};
+///
+/// let user_id = create_signal(1u32);
+/// let user = create_resource_with_source(user_id, |id| async move {
+///     fetch_user(id).await
+/// });
+///
+/// user_id.set(2); // Starts a new fetch; a late response for user 1 is ignored.
+/// ```
+pub fn create_resource_with_source<S, T, E, F>(source: Signal<S>, fetcher: F) -> Resource<T, E>
+where
+    S: SignalValue + PartialEq + Clone + 'static,
+    T: SignalValue + PartialEq + 'static,
+    E: PartialEq + Send + 'static,
+    F: AsyncFn(S) -> Result<T, E> + Send + Clone + 'static,
+{
+    create_resource_inner(move || source.get(), fetcher)
+}
+
+fn create_resource_inner<S, T, E, F>(
+    read_source: impl Fn() -> S + Send + 'static,
+    fetcher: F,
+) -> Resource<T, E>
+where
+    S: SignalValue + PartialEq + Clone + 'static,
+    T: SignalValue + PartialEq + 'static,
+    E: PartialEq + Send + 'static,
+    F: AsyncFn(S) -> Result<T, E> + Send + Clone + 'static,
+{
+    let value = create_signal(None);
+    let status = create_signal(ResourceStatus::Idle);
+    let tracker = create_signal(ResourceTracker::<S> {
+        version: 0,
+        source: None,
+        in_flight: None,
+    });
+
+    create_effect(move || {
+        let source = read_source();
+        let requested = status
+            .with(|s| matches!(s, ResourceStatus::Idle | ResourceStatus::Pending))
+            .unwrap_or(false);
+        let source_changed = tracker
+            .with_untracked(|t| t.source.as_ref().is_some_and(|s| *s != source))
+            .unwrap_or(false);
+        if (!requested && !source_changed) || !has_spawner() {
+            return;
+        }
+
+        let version = tracker.with_untracked(|t| t.version + 1).unwrap_or(1);
+        let fetcher = fetcher.clone();
+        let (fetch, handle) = Abortable::new(Box::pin({
+            let source = source.clone();
+            async move {
+                let result = fetcher(source).await;
+                settle_resource(tracker, value, status, version, result);
+            }
+        }));
+        let superseded = tracker.with_untracked(|t| t.in_flight.clone()).flatten();
+        tracker.set(ResourceTracker {
+            version,
+            source: Some(source),
+            in_flight: Some(handle),
+        });
+        if let Some(superseded) = superseded {
+            superseded.abort();
+        }
+
+        status.set(ResourceStatus::Loading);
+        spawn(fetch);
+    });
+
+    Resource { status, value }
+}
+
+/// Store the outcome of a fetch unless a newer fetch was started meanwhile.
+fn settle_resource<S, T, E>(
+    tracker: Signal<ResourceTracker<S>>,
+    value: Signal<Option<T>>,
+    status: Signal<ResourceStatus<E>>,
+    version: u64,
+    result: Result<T, E>,
+) where
+    S: SignalValue + 'static,
+    T: SignalValue + PartialEq + 'static,
+    E: PartialEq + Send + 'static,
+{
+    if tracker.with_untracked(|t| t.version) != Some(version) {
+        return;
+    }
+    match result {
+        Ok(resolved) => {
+            value.set(Some(resolved));
+            status.set(ResourceStatus::Resolved);
+        }
+        Err(err) => status.set(ResourceStatus::Errored(err)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    extern crate std;
+
+    use super::*;
+    use alloc::{string::String, sync::Arc, vec};
+    use core::sync::atomic::{AtomicUsize, Ordering};
+
+    #[test]
+    fn test_nested_scopes() {
+        let _guard = TEST_MUTEX.lock();
+        reset_runtime_state();
+
+        run_scope(
+            || {
+                let outer_signal = create_signal(0);
+
+                run_scope(
+                    move || {
+                        let inner_signal = create_signal("hello");
+                        assert!(inner_signal.get() == "hello");
+                        outer_signal.set(42);
+                        Node::Empty
+                    },
+                    |_| {},
+                );
+
+                assert_eq!(outer_signal.get(), 42);
+                Node::Empty
+            },
+            |_| {},
+        );
+    }
+
+    #[test]
+    fn test_signal_and_effect_in_scope() {
+        let _guard = TEST_MUTEX.lock();
+        reset_runtime_state();
+
+        run_scope(
+            move || {
+                let effect_count = Arc::new(AtomicUsize::new(0));
+                let effect_count_clone = effect_count.clone();
+                let signal = create_signal(0);
+
+                create_effect(move || {
+                    let _ = signal.get();
+                    effect_count_clone.fetch_add(1, Ordering::SeqCst);
+                    assert!(effect_count.load(Ordering::SeqCst) > 0);
+                    signal.set(1);
+                });
+
+                Node::Empty
+            },
+            |_| {},
+        );
+    }
+
+    #[test]
+    fn test_multiple_signals_and_dependencies() {
+        let _guard = TEST_MUTEX.lock();
+        reset_runtime_state();
+
+        run_scope(
+            || {
+                let signal1 = create_signal("hello");
+                let signal2 = create_signal(0);
+
+                create_effect(move || {
+                    let _str_val = signal1.get();
+                    let _num_val = signal2.get();
+                });
+
+                signal1.set("world");
+                signal2.set(42);
+
+                assert_eq!(signal1.get(), "world");
+                assert_eq!(signal2.get(), 42);
+
+                Node::Empty
+            },
+            |_| {},
+        );
+    }
+
+    #[test]
+    fn stale_effects_are_removed_after_rerender() {
+        let _guard = TEST_MUTEX.lock();
+        reset_runtime_state();
+
+        let handles = Arc::new(spin::Mutex::new(None));
+        let had_effect = Arc::new(AtomicUsize::new(0));
+
+        run_scope(
+            {
+                let handles = handles.clone();
+                let had_effect = had_effect.clone();
+
+                move || {
+                    let enabled = create_signal(true);
+                    let trigger = create_signal(0);
+
+                    *handles.lock() = Some((enabled, trigger));
+
+                    if enabled.get() {
+                        create_effect(move || {
+                            let _ = trigger.get();
+                        });
+
+                        had_effect.store(1, Ordering::SeqCst);
+                    }
+
+                    Node::Empty
+                }
+            },
+            |_| {},
+        );
+
+        let (enabled, _) = handles.lock().as_ref().copied().unwrap();
+        let scope_id = enabled.id.0;
+
+        assert_eq!(had_effect.load(Ordering::SeqCst), 1);
+
+        enabled.set(false);
+        render_scope(scope_id);
+
+        assert!(
+            runtime()
+                .lock()
+                .scope(scope_id)
+                .map(|s| s.effects.iter().all(|e| e.is_none()))
+                .unwrap_or(true)
+        );
+    }
+
+    #[test]
+    fn transient_scope_disposes_runtime_state() {
+        let _guard = TEST_MUTEX.lock();
+        reset_runtime_state();
+
+        run_scope_transient(
+            || {
+                let signal = create_signal(1);
+                create_effect(move || {
+                    let _ = signal.get();
+                });
+                Node::Empty
+            },
+            |_| {},
+        );
+
+        let rt = runtime().lock();
+        // Transient scopes clear their data but keep the slot for reuse
+        for scope in rt.scopes.iter().flatten() {
+            assert_eq!(scope.signal_count, 0);
+            assert_eq!(scope.effect_count, 0);
+            assert!(scope.signals.is_empty());
+            assert!(scope.effects.is_empty());
+        }
+        // Dep tracking is skipped in transient mode, so these stay empty
+        assert!(rt.scope_dependencies.is_empty());
+        assert!(rt.signal_dependencies.is_empty());
+    }
+
+    #[test]
+    fn transient_scopes_run_effect_cleanups_on_teardown() {
+        use core::cell::RefCell;
+
+        std::thread_local! {
+            static TASKS: RefCell<Vec<LocalFuture>> = const { RefCell::new(Vec::new()) };
+        }
+
+        let _guard = TEST_MUTEX.lock();
+        reset_runtime_state();
+        set_spawner(|future| TASKS.with(|tasks| tasks.borrow_mut().push(future)));
+
+        let log = Arc::new(spin::Mutex::new(Vec::new()));
+        let captured = log.clone();
+        run_scope_transient(
+            move || {
+                let log = captured.clone();
+                create_effect(|| {});
+                create_effect_with_cleanup(move || {
+                    log.lock().push("effect");
+                    let log = log.clone();
+                    move || log.lock().push("cleanup")
+                });
+                let log = captured.clone();
+                create_async_effect(async move || {
+                    core::future::pending::<()>().await;
+                    log.lock().push("resumed");
+                });
+                Node::Empty
+            },
+            |_| {},
+        );
+        assert_eq!(*log.lock(), ["effect", "cleanup"]);
+
+        // The async effect was aborted, so its task ends without resuming.
+        let mut cx = Context::from_waker(Waker::noop());
+        for mut task in TASKS.with(|tasks| tasks.take()) {
+            assert!(task.as_mut().poll(&mut cx).is_ready());
+        }
+        assert_eq!(*log.lock(), ["effect", "cleanup"]);
+        *SPAWNER.lock() = None;
+    }
+
+    #[test]
+    fn context_is_visible_to_descendant_scopes() {
+        let _guard = TEST_MUTEX.lock();
+        reset_runtime_state();
+
+        let seen = Arc::new(spin::Mutex::new(Vec::new()));
+
+        run_scope(
+            {
+                let seen = seen.clone();
+                move || {
+                    provide_context(7_u32);
+                    let seen = seen.clone();
+                    run_scope(
+                        move || {
+                            seen.lock().push(use_context::<u32>());
+                            seen.lock().push(use_context::<i64>().map(|_| 0));
+                            Node::Empty
+                        },
+                        |_| {},
+                    );
+                    Node::Empty
+                }
+            },
+            |_| {},
+        );
+
+        assert_eq!(*seen.lock(), vec![Some(7), None]);
+        assert_eq!(use_context::<u32>(), None);
+    }
+
+    #[test]
+    fn transient_scope_context_is_dropped_after_render() {
+        let _guard = TEST_MUTEX.lock();
+        reset_runtime_state();
+
+        let html = run_scope_transient(
+            || {
+                provide_context(String::from("ssr"));
+                run_scope_transient(
+                    || Node::from(use_context::<String>().unwrap_or_default()),
+                    |_| {},
+                )
+            },
+            |_| {},
+        )
+        .to_html();
+
+        assert_eq!(html, "ssr");
+        let rt = runtime().lock();
+        assert!(rt.scopes.iter().flatten().all(|s| s.contexts.is_empty()));
+    }
+
+    fn live_signal_count(rt: &RuntimeState) -> usize {
+        rt.scopes.iter().flatten().map(|s| s.signals.len()).sum()
+    }
+
+    #[test]
+    fn child_scopes_are_disposed_when_parent_rerenders() {
+        let _guard = TEST_MUTEX.lock();
+        reset_runtime_state();
+
+        let cleanups = Arc::new(AtomicUsize::new(0));
+        let captured = cleanups.clone();
+        let (count, _) = in_root_scope(move || {
+            let count = create_signal(0);
+            let _ = count.get();
+
+            for _ in 0..3 {
+                let cleanups = captured.clone();
+                run_scope(
+                    move || {
+                        let local = create_signal(String::from("row"));
+                        let cleanups = cleanups.clone();
+                        create_effect_with_cleanup(move || {
+                            let _ = local.get();
+                            let cleanups = cleanups.clone();
+                            move || {
+                                cleanups.fetch_add(1, Ordering::SeqCst);
+                            }
+                        });
+                        Node::Empty
+                    },
+                    |_| {},
+                );
+            }
+            count
+        });
+        let (scopes_after_first_render, signals_after_first_render) = {
+            let rt = runtime().lock();
+            (rt.scopes.len(), live_signal_count(&rt))
+        };
+
+        for i in 1..=2000 {
+            count.set(i);
+        }
+
+        let rt = runtime().lock();
+        assert_eq!(cleanups.load(Ordering::SeqCst), 3 * 2000);
+        assert_eq!(rt.scopes.len(), scopes_after_first_render);
+        assert_eq!(live_signal_count(&rt), signals_after_first_render);
+        assert_eq!(rt.scopes.iter().flatten().count(), 4);
+    }
+
+    #[test]
+    fn scope_handle_dispose_tears_down_descendants() {
+        let _guard = TEST_MUTEX.lock();
+        reset_runtime_state();
+
+        let cleanups = Arc::new(AtomicUsize::new(0));
+        let captured = cleanups.clone();
+        let ((), handle) = in_root_scope(move || {
+            let cleanups = captured.clone();
+            run_scope(
+                move || {
+                    let cleanups = cleanups.clone();
+                    create_effect_with_cleanup(move || {
+                        let cleanups = cleanups.clone();
+                        move || {
+                            cleanups.fetch_add(1, Ordering::SeqCst);
+                        }
+                    });
+                    Node::Empty
+                },
+                |_| {},
+            );
+        });
+        assert!(handle.is_alive());
+        handle.dispose();
+
+        assert!(!handle.is_alive());
+        assert_eq!(cleanups.load(Ordering::SeqCst), 1);
+        let rt = runtime().lock();
+        assert!(rt.scopes.iter().all(|s| s.is_none()));
+        assert!(rt.scope_dependencies.is_empty());
+        assert!(rt.signal_dependencies.is_empty());
+    }
+
+    #[test]
+    fn non_wasm_resources_do_not_enter_loading_state() {
+        let _guard = TEST_MUTEX.lock();
+        reset_runtime_state();
+
+        run_scope_transient(
+            || {
+                let resource = create_resource(|| async { 1_u32 });
+                assert_eq!(resource.status().get(), ResourceStatus::Idle);
+                Node::Empty
+            },
+            |_| {},
+        );
+    }
+
+    #[test]
+    fn diamond_computed_runs_once_with_consistent_inputs() {
+        let _guard = TEST_MUTEX.lock();
+        reset_runtime_state();
+
+        let runs = Arc::new(AtomicUsize::new(0));
+        let runs_clone = runs.clone();
+        let ((source, sum), _) = in_root_scope(move || {
+            let source = create_signal(1);
+            let left = create_computed(move || source.get() + 1);
+            let right = create_computed(move || source.get() * 2);
+            let runs = runs_clone.clone();
+            let sum = create_computed(move || {
+                let (left, right) = (left.get(), right.get());
+                assert_eq!(right, (left - 1) * 2, "observed a half-updated graph");
+                runs.fetch_add(1, Ordering::SeqCst);
+                left + right
+            });
+            let _ = sum.get();
+            (source, sum)
+        });
+        assert_eq!(runs.load(Ordering::SeqCst), 1);
+
+        source.set(5);
+        assert_eq!(sum.get(), 16);
+        assert_eq!(runs.load(Ordering::SeqCst), 2);
+
+        // Reading again without changes reuses the cached value.
+        assert_eq!(sum.get(), 16);
+        assert_eq!(runs.load(Ordering::SeqCst), 2);
+    }
+
+    #[test]
+    fn computed_runs_once_per_batch() {
+        let _guard = TEST_MUTEX.lock();
+        reset_runtime_state();
+
+        let runs = Arc::new(AtomicUsize::new(0));
+        let renders = Arc::new(AtomicUsize::new(0));
+        let (runs_clone, renders_clone) = (runs.clone(), renders.clone());
+        let ((first, second, total), _) = in_root_scope(move || {
+            renders_clone.fetch_add(1, Ordering::SeqCst);
+            let first = create_signal(1);
+            let second = create_signal(2);
+            let runs = runs_clone.clone();
+            let total = create_computed(move || {
+                runs.fetch_add(1, Ordering::SeqCst);
+                first.get() + second.get()
+            });
+            let _ = total.get();
+            (first, second, total)
+        });
+
+        batch(|| {
+            first.set(10);
+            second.set(20);
+        });
+        assert_eq!(runs.load(Ordering::SeqCst), 2);
+        assert_eq!(renders.load(Ordering::SeqCst), 2);
+        assert_eq!(total.get(), 30);
+
+        // Changes that cancel out recompute the node but do not re-render.
+        batch(|| {
+            first.set(20);
+            second.set(10);
+        });
+        assert_eq!(runs.load(Ordering::SeqCst), 3);
+        assert_eq!(renders.load(Ordering::SeqCst), 2);
+    }
+
+    #[test]
+    fn changes_outside_a_render_do_not_requeue_readers_on_later_renders() {
+        let _guard = TEST_MUTEX.lock();
+        reset_runtime_state();
+
+        let renders = Arc::new(AtomicUsize::new(0));
+        let counter = renders.clone();
+        let ((a, b), _) = in_root_scope(move || {
+            let a = create_signal(0);
+            let b = create_signal(0);
+            let counter = counter.clone();
+            run_scope(
+                move || {
+                    counter.fetch_add(1, AtomicOrdering::Relaxed);
+                    let _ = a.get();
+                    Node::Empty
+                },
+                |_| {},
+            );
+            run_scope(
+                move || {
+                    let _ = b.get();
+                    Node::Empty
+                },
+                |_| {},
+            );
+            (a, b)
+        });
+
+        a.set(1);
+        assert_eq!(renders.load(AtomicOrdering::Relaxed), 2);
+        assert!(runtime().lock().scope_signal_changes.is_empty());
+
+        // Rendering an unrelated scope must not bring back the change to `a`.
+        b.set(1);
+        assert_eq!(renders.load(AtomicOrdering::Relaxed), 2);
+    }
+
+    #[test]
+    fn nested_batches_render_once_when_the_outermost_ends() {
+        let _guard = TEST_MUTEX.lock();
+        reset_runtime_state();
+
+        let renders = Arc::new(AtomicUsize::new(0));
+        let renders_clone = renders.clone();
+        let (count, _) = in_root_scope(move || {
+            renders_clone.fetch_add(1, Ordering::SeqCst);
+            let count = create_signal(0);
+            let _ = count.get();
+            count
+        });
+
+        batch(|| {
+            batch(|| count.set(1));
+            assert_eq!(renders.load(Ordering::SeqCst), 1);
+            count.set(2);
+        });
+        assert_eq!(renders.load(Ordering::SeqCst), 2);
+    }
+
+    #[test]
+    fn failed_transactions_restore_previous_values() {
+        let _guard = TEST_MUTEX.lock();
+        reset_runtime_state();
+
+        let ((likes, tags), _) = in_root_scope(move || {
+            let likes = create_signal(10);
+            let tags = create_signal(BTreeSet::from(["rust"]));
+            let _ = (likes.get(), tags.len());
+            (likes, tags)
+        });
+
+        let result: Result<(), &str> = transaction(|| {
+            likes.set(11);
+            tags.remove(&"rust");
+            likes.set(12);
+            Err("request failed")
+        });
+        assert_eq!(result, Err("request failed"));
+        assert_eq!(likes.get(), 10);
+        assert_eq!(tags.get(), BTreeSet::from(["rust"]));
+
+        // A failed inner transaction keeps the outer one's changes, while a
+        // failed outer one also undoes what inner transactions committed.
+        let _ = transaction(|| {
+            likes.set(11);
+            let _ = transaction(|| {
+                likes.set(20);
+                Err::<(), ()>(())
+            });
+            assert_eq!(likes.get(), 11);
+            transaction(|| {
+                tags.insert("wasm");
+                Ok::<(), ()>(())
+            })
+        });
+        assert_eq!((likes.get(), tags.len()), (11, 2));
+
+        let _ = transaction::<(), ()>(|| {
+            transaction(|| {
+                likes.set(50);
+                tags.clear();
+                Ok::<(), ()>(())
+            })?;
+            Err(())
+        });
+        assert_eq!((likes.get(), tags.len()), (11, 2));
+    }
+    #[test]
+    fn panicking_batches_and_transactions_are_closed() {
+        let _guard = TEST_MUTEX.lock();
+        reset_runtime_state();
+
+        let renders = Arc::new(AtomicUsize::new(0));
+        let counter = renders.clone();
+        let (count, _) = in_root_scope(move || {
+            counter.fetch_add(1, Ordering::SeqCst);
+            let count = create_signal(0);
+            let _ = count.get();
+            count
+        });
+
+        let panicked = std::panic::catch_unwind(core::panic::AssertUnwindSafe(|| {
+            transaction(|| -> Result<(), ()> {
+                count.set(1);
+                panic!("handler failed");
+            })
+        }));
+        assert!(panicked.is_err());
+        assert_eq!(count.get(), 0);
+
+        let panicked = std::panic::catch_unwind(|| batch(|| panic!("handler failed")));
+        assert!(panicked.is_err());
+
+        {
+            let rt = runtime().lock();
+            assert_eq!(rt.batch_depth, 0);
+            assert!(rt.transactions.is_empty());
+        }
+        count.set(2);
+        assert_eq!(renders.load(Ordering::SeqCst), 2);
+    }
+
+    #[test]
+    fn stale_resource_responses_are_discarded() {
+        let _guard = TEST_MUTEX.lock();
+        reset_runtime_state();
+
+        run_scope(
+            || {
+                let tracker = create_signal(ResourceTracker {
+                    version: 2,
+                    source: Some(2u32),
+                    in_flight: None,
+                });
+                let resource = Resource::<String, String> {
+                    value: create_signal(None),
+                    status: create_signal(ResourceStatus::Loading),
+                };
+
+                // The response for the first source arrives after the second fetch started.
+                settle_resource(
+                    tracker,
+                    resource.value,
+                    resource.status,
+                    1,
+                    Ok("first".to_string()),
+                );
+                assert_eq!(resource.get(), None);
+                assert_eq!(resource.status().get(), ResourceStatus::Loading);
+
+                settle_resource(
+                    tracker,
+                    resource.value,
+                    resource.status,
+                    2,
+                    Err("not found".to_string()),
+                );
+                assert_eq!(resource.error(), Some("not found".to_string()));
+                assert_eq!(resource.get(), None);
+
+                Node::Empty
+            },
+            |_| {},
+        );
+    }
+
+    #[test]
+    fn resource_refetches_when_its_source_changes() {
+        use core::cell::RefCell;
+
+        std::thread_local! {
+            static TASKS: RefCell<Vec<LocalFuture>> = const { RefCell::new(Vec::new()) };
+        }
+        fn poll_task(mut task: LocalFuture) {
+            let mut cx = Context::from_waker(core::task::Waker::noop());
+            assert!(task.as_mut().poll(&mut cx).is_ready());
+        }
+
+        let _guard = TEST_MUTEX.lock();
+        reset_runtime_state();
+        set_spawner(|future| TASKS.with(|tasks| tasks.borrow_mut().push(future)));
+
+        let ((user_id, user), _) = in_root_scope(move || {
+            let user_id = create_signal(1u32);
+            let user = create_resource_with_source(user_id, |id: u32| async move {
+                if id == 0 { Err("missing") } else { Ok(id * 10) }
+            });
+            (user_id, user)
+        });
+        assert_eq!(user.status().get(), ResourceStatus::Loading);
+
+        user_id.set(2);
+        let mut tasks = TASKS.with(|tasks| tasks.take());
+        assert_eq!(tasks.len(), 2);
+
+        // The newer fetch finishes first; the late response for id 1 is dropped.
+        poll_task(tasks.pop().unwrap());
+        poll_task(tasks.pop().unwrap());
+        assert_eq!(user.get(), Some(20));
+        assert_eq!(user.status().get(), ResourceStatus::Resolved);
+
+        user_id.set(0);
+        for task in TASKS.with(|tasks| tasks.take()) {
+            poll_task(task);
+        }
+        assert_eq!(user.error(), Some("missing"));
+        assert_eq!(user.get(), Some(20));
+
+        *SPAWNER.lock() = None;
+    }
+
+    #[test]
+    fn resource_aborts_a_fetch_superseded_by_a_new_source() {
+        use core::cell::RefCell;
+
+        std::thread_local! {
+            static TASKS: RefCell<Vec<LocalFuture>> = const { RefCell::new(Vec::new()) };
+        }
+        /// Requests whose response has arrived.
+        static ARRIVED: spin::Mutex<Vec<u32>> = spin::Mutex::new(Vec::new());
+        /// Fetches that ran to completion.
+        static FINISHED: spin::Mutex<Vec<u32>> = spin::Mutex::new(Vec::new());
+        /// Pending until the response for request `.0` has arrived.
+        struct Response(u32);
+        impl Future for Response {
+            type Output = ();
+            fn poll(self: Pin<&mut Self>, _: &mut Context<'_>) -> Poll<()> {
+                if ARRIVED.lock().contains(&self.0) {
+                    Poll::Ready(())
+                } else {
+                    Poll::Pending
+                }
+            }
+        }
+        fn poll_task(task: &mut LocalFuture) -> Poll<()> {
+            let mut cx = Context::from_waker(core::task::Waker::noop());
+            task.as_mut().poll(&mut cx)
+        }
+
+        let _guard = TEST_MUTEX.lock();
+        reset_runtime_state();
+        ARRIVED.lock().clear();
+        FINISHED.lock().clear();
+        set_spawner(|future| TASKS.with(|tasks| tasks.borrow_mut().push(future)));
+
+        let ((id, item), _) = in_root_scope(move || {
+            let id = create_signal(1u32);
+            let item = create_resource_with_source(id, |id: u32| async move {
+                Response(id).await;
+                FINISHED.lock().push(id);
+                Ok::<_, ()>(alloc::format!("item {id}"))
+            });
+            (id, item)
+        });
+
+        let mut first = TASKS.with(|tasks| tasks.borrow_mut().pop()).unwrap();
+        assert!(poll_task(&mut first).is_pending());
+        assert_eq!(item.status().get(), ResourceStatus::Loading);
+
+        // The source changes while the first request is still in flight.
+        id.set(2);
+        let mut second = TASKS.with(|tasks| tasks.borrow_mut().pop()).unwrap();
+        assert!(poll_task(&mut second).is_pending());
+
+        // Both responses arrive; the newer fetch is polled first.
+        ARRIVED.lock().extend([1, 2]);
+        assert!(poll_task(&mut second).is_ready());
+        assert_eq!(item.get(), Some("item 2".to_string()));
+        assert_eq!(item.status().get(), ResourceStatus::Resolved);
+
+        // The superseded fetch was aborted and never runs to completion.
+        assert!(poll_task(&mut first).is_ready());
+        assert_eq!(*FINISHED.lock(), [2]);
+        assert_eq!(item.get(), Some("item 2".to_string()));
+
+        *SPAWNER.lock() = None;
+    }
+
+    #[test]
+    fn async_effect_aborts_the_previous_run() {
+        use core::cell::RefCell;
+
+        std::thread_local! {
+            static TASKS: RefCell<Vec<LocalFuture>> = const { RefCell::new(Vec::new()) };
+        }
+        /// Pending on the first poll, like a real request in flight.
+        struct YieldOnce(bool);
+        impl Future for YieldOnce {
+            type Output = ();
+            fn poll(mut self: Pin<&mut Self>, _: &mut Context<'_>) -> Poll<()> {
+                if core::mem::replace(&mut self.0, true) {
+                    Poll::Ready(())
+                } else {
+                    Poll::Pending
+                }
+            }
+        }
+        fn run_tasks() {
+            let mut cx = Context::from_waker(Waker::noop());
+            for mut task in TASKS.with(|tasks| tasks.take()) {
+                assert!(task.as_mut().poll(&mut cx).is_ready());
+            }
+        }
+
+        let _guard = TEST_MUTEX.lock();
+        reset_runtime_state();
+        set_spawner(|future| TASKS.with(|tasks| tasks.borrow_mut().push(future)));
+
+        let finished = Arc::new(spin::Mutex::new(Vec::new()));
+        let finished_clone = finished.clone();
+        let (query, handle) = in_root_scope(move || {
+            let query = create_signal(1);
+            let finished = finished_clone.clone();
+            create_async_effect(async move || {
+                let query = query.get();
+                YieldOnce(false).await;
+                finished.lock().push(query);
+            });
+            query
+        });
+
+        // The query changes while the first run is in flight.
+        query.set(2);
+        run_tasks();
+        assert_eq!(*finished.lock(), vec![2]);
+
+        query.set(3);
+        handle.dispose();
+        run_tasks();
+        assert_eq!(*finished.lock(), vec![2]);
+
+        *SPAWNER.lock() = None;
     }
-}
 
-#[allow(dead_code)]
-fn dispose_scope(scope_id: usize) {
-    clear_scope_effects(scope_id);
+    #[test]
+    fn untracked_reads_do_not_subscribe_the_scope() {
+        let _guard = TEST_MUTEX.lock();
+        reset_runtime_state();
+
+        let renders = Arc::new(AtomicUsize::new(0));
+        let renders_clone = renders.clone();
+        let ((tracked, peeked, untracked), _) = in_root_scope(move || {
+            renders_clone.fetch_add(1, Ordering::SeqCst);
+            let tracked = create_signal(0);
+            let peeked = create_signal(0);
+            let untracked = create_signal(0);
+            let _ = tracked.get() + peeked.peek() + untrack(|| untracked.get());
+            (tracked, peeked, untracked)
+        });
 
-    let mut rt = RUNTIME.lock();
+        peeked.set(1);
+        untracked.set(1);
+        assert_eq!(renders.load(Ordering::SeqCst), 1);
+        assert!(
+            runtime()
+                .lock()
+                .signal_dependencies
+                .get(&peeked.id)
+                .is_none_or(Vec::is_empty)
+        );
 
-    if scope_id <= rt.scopes.len() {
-        rt.scopes[scope_id - 1] = None;
+        tracked.set(1);
+        assert_eq!(renders.load(Ordering::SeqCst), 2);
     }
 
-    rt.pending_scope_renders.retain(|s| *s != scope_id);
-    rt.scope_signal_changes.retain(|(s, _)| *s != scope_id);
+    #[test]
+    fn panicking_writes_put_the_value_back() {
+        let _guard = TEST_MUTEX.lock();
+        reset_runtime_state();
+
+        /// Panics when compared, like a faulty `PartialEq`.
+        struct Fragile(u32);
+        impl PartialEq for Fragile {
+            fn eq(&self, _: &Self) -> bool {
+                panic!("compare failed")
+            }
+        }
+        struct Form(Fragile);
+        impl Store for Form {
+            type Fields = Lens<Form>;
+
+            fn fields(lens: Lens<Self>) -> Lens<Self> {
+                lens
+            }
+        }
+
+        let ((count, form), _) = in_root_scope(move || {
+            let count = create_signal(1);
+            let form = create_store(Form(Fragile(1)));
+            (count, form)
+        });
 
-    let sig_dep_keys: Vec<_> = rt
-        .signal_dependencies
-        .keys()
-        .filter(|(s, _)| *s == scope_id)
-        .copied()
-        .collect();
-    for key in sig_dep_keys {
-        rt.signal_dependencies.remove(&key);
+        let panicked = std::panic::catch_unwind(|| count.update(|_| panic!("update failed")));
+        assert!(panicked.is_err());
+        assert_eq!(count.try_get(), Ok(1));
+        count.update(|value| *value += 1);
+        assert_eq!(count.get(), 2);
+
+        let field = form.field(0, |f| &f.0, |f| &mut f.0);
+        let panicked =
+            std::panic::catch_unwind(core::panic::AssertUnwindSafe(|| field.set(Fragile(2))));
+        assert!(panicked.is_err());
+        assert_eq!(field.try_with(|value| value.0), Ok(1));
     }
 
-    for subs in rt.signal_dependencies.values_mut() {
-        subs.retain(|s| *s != scope_id);
+    #[test]
+    fn untrack_restores_tracking_after_a_panic() {
+        let _guard = TEST_MUTEX.lock();
+        reset_runtime_state();
+
+        let panicked = std::panic::catch_unwind(|| untrack(|| panic!("read failed")));
+        assert!(panicked.is_err());
+        assert!(!runtime().lock().untracked);
     }
-    rt.scope_dependencies.remove(&scope_id);
-}
 
-struct ScopeGuard {
-    previous_scope: Option<usize>,
-}
+    #[test]
+    fn watch_reports_new_and_previous_values_on_change() {
+        let _guard = TEST_MUTEX.lock();
+        reset_runtime_state();
 
-impl Drop for ScopeGuard {
-    fn drop(&mut self) {
-        RUNTIME.lock().current_scope = self.previous_scope;
-        IN_SCOPE.store(self.previous_scope.is_some(), AtomicOrdering::Relaxed);
+        let seen = Arc::new(spin::Mutex::new(Vec::new()));
+        let renders = Arc::new(AtomicUsize::new(0));
+        let (seen_clone, counter) = (seen.clone(), renders.clone());
+        let (count, _) = in_root_scope(move || {
+            counter.fetch_add(1, Ordering::SeqCst);
+            let count = create_signal(0);
+            let last = create_signal(0);
+            let seen = seen_clone.clone();
+            watch(count, move |new, old| {
+                seen.lock().push((*new, *old));
+                if *new > 3 {
+                    last.set(*new);
+                }
+            });
+            let _ = last.get();
+            count
+        });
+        assert!(seen.lock().is_empty());
+
+        // Only the watcher is subscribed, not the scope calling `watch`.
+        count.set(1);
+        count.set(1);
+        assert_eq!(renders.load(Ordering::SeqCst), 1);
+        batch(|| {
+            count.set(2);
+            count.set(3);
+        });
+        assert_eq!(renders.load(Ordering::SeqCst), 1);
+
+        // Writes made by the callback re-render their readers.
+        count.set(5);
+        assert_eq!(renders.load(Ordering::SeqCst), 2);
+        count.set(6);
+        assert_eq!(*seen.lock(), vec![(1, 0), (3, 1), (5, 3), (6, 5)]);
+        assert_eq!(renders.load(Ordering::SeqCst), 3);
     }
-}
 
-fn render_scope(scope_id: usize) -> Node {
-    let (previous_scope, scope_fn) = {
-        let mut rt = RUNTIME.lock();
+    #[cfg(any(feature = "memoization", feature = "full-reactivity"))]
+    #[test]
+    fn memos_keep_their_value_across_rerenders_and_go_with_their_scope() {
+        let _guard = TEST_MUTEX.lock();
+        reset_runtime_state();
 
-        let previous_scope = rt.current_scope;
+        let runs = Arc::new(AtomicUsize::new(0));
+        let counter = runs.clone();
+        let ((rows, title), handle) = in_root_scope(move || {
+            let rows = create_signal(2);
+            let title = create_signal("rows");
+            let counter = counter.clone();
+            let doubled = create_memo(move || {
+                counter.fetch_add(1, Ordering::SeqCst);
+                rows.get() * 2
+            });
+            let _ = (title.get(), doubled.get());
+            (rows, title)
+        });
+        let runs = || runs.load(Ordering::SeqCst);
+        assert_eq!(runs(), 1);
 
-        if rt.rendering_scope == scope_id {
-            rt.push_pending_render(scope_id);
-            return Node::Empty;
-        }
+        title.set("items");
+        assert_eq!(runs(), 1);
+        rows.set(3);
+        assert_eq!(runs(), 2);
 
-        rt.current_scope = Some(scope_id);
-        IN_SCOPE.store(true, AtomicOrdering::Relaxed);
+        handle.dispose();
+        assert_eq!(live_signal_count(&runtime().lock()), 0);
+    }
 
-        let has_dependencies = rt.scope_dependencies.contains_key(&scope_id);
-        if has_dependencies {
-            let has_changes = rt
-                .signal_dependencies
-                .iter()
-                .any(|(_, scopes)| scopes.contains(&scope_id));
-            if !has_changes {
-                rt.current_scope = previous_scope;
-                return Node::Empty;
+    #[test]
+    fn memo_cache_stays_bounded_over_many_list_row_renders() {
+        let _guard = TEST_MUTEX.lock();
+        reset_runtime_state();
+
+        let computed = Arc::new(AtomicUsize::new(0));
+        let counter = computed.clone();
+        let ((first_row, labels), handle) = in_root_scope(move || {
+            let first_row = create_signal(0u32);
+            let labels = create_memo_cache::<u32, String>(Some(8));
+            // Each row is a fresh child scope on every render.
+            for id in first_row.get()..first_row.get() + 5 {
+                let counter = counter.clone();
+                run_scope(
+                    move || {
+                        let label = labels.get_or_compute(id, || {
+                            counter.fetch_add(1, Ordering::SeqCst);
+                            alloc::format!("Row {id}")
+                        });
+                        Node::Text(label)
+                    },
+                    |_| {},
+                );
             }
-        }
+            (first_row, labels)
+        });
+        let computed = || computed.load(Ordering::SeqCst);
+        assert_eq!((labels.len(), computed()), (5, 5));
 
-        rt.rendering_scope = scope_id;
-        rt.scope_signal_changes
-            .retain(|&(scope, _)| scope != scope_id);
+        // Rows 0..5 again: every label comes from the cache.
+        first_row.set(0);
+        first_row.update(|_| {});
+        assert_eq!((labels.len(), computed()), (5, 5));
 
-        if let Some(signal_ids) = rt.scope_dependencies.remove(&scope_id) {
-            for signal_id in signal_ids {
-                if let Some(scopes) = rt.signal_dependencies.get_mut(&signal_id) {
-                    scopes.retain(|s| *s != scope_id);
-                }
-            }
+        for first in 1..100 {
+            first_row.set(first);
+            assert!(labels.len() <= 8);
         }
+        assert_eq!(labels.len(), 8);
+        // Each render adds one new row; the other four are cached.
+        assert_eq!(computed(), 5 + 99);
 
-        let scope_fn = rt.scope_mut(scope_id).and_then(|s| s.function.take());
+        handle.dispose();
+        assert_eq!(labels.len(), 0);
+        assert_eq!(live_signal_count(&runtime().lock()), 0);
+    }
 
-        if let Some(scope) = rt.scope_mut(scope_id) {
-            scope.effects.clear();
-            scope.effect_cleanups.clear();
-            scope.effect_executing.clear();
-            scope.effect_count = 0;
-        }
+    #[test]
+    fn memo_cache_drops_the_least_recently_used_value() {
+        let _guard = TEST_MUTEX.lock();
+        reset_runtime_state();
 
-        (previous_scope, scope_fn)
-    }; // Lock released here before calling user code
+        let (cache, _) = in_root_scope(move || create_memo_cache::<u32, u32>(Some(2)));
+        assert_eq!(cache.get_or_compute(1, || 10), 10);
+        assert_eq!(cache.get_or_compute(2, || 20), 20);
+        // Using 1 again makes 2 the least recently used value.
+        assert_eq!(cache.get_or_compute(1, || unreachable!()), 10);
+        assert_eq!(cache.get_or_compute(3, || 30), 30);
+        assert_eq!(cache.len(), 2);
+        assert_eq!(cache.get_or_compute(1, || unreachable!()), 10);
+        assert_eq!(cache.get_or_compute(2, || 21), 21);
+
+        cache.clear();
+        assert!(cache.is_empty());
+    }
 
-    let _guard = ScopeGuard { previous_scope };
+    #[cfg(feature = "serde")]
+    #[test]
+    fn serialized_signals_round_trip_through_hydration_state() {
+        let _guard = TEST_MUTEX.lock();
+        reset_runtime_state();
 
-    let node = scope_fn.map(|mut fnc| {
-        let mut node = fnc();
+        run_scope_transient(
+            || {
+                let count = create_serialized_signal("count", 1);
+                count.set(2);
+                Node::Empty
+            },
+            |_| {},
+        );
+        let state = take_hydration_state().unwrap();
+        assert_eq!(state, "{\"count\":2}");
 
-        let callback = {
-            let mut rt = RUNTIME.lock();
-            let scope = rt.ensure_scope(scope_id);
-            scope.function = Some(fnc);
-            if let Some(el) = node.as_element_mut() {
-                el.key = scope_id.to_string();
-            }
-            scope.callback.clone()
-        };
+        reset_runtime_state();
+        restore_hydration_state(&state).unwrap();
+        let html = run_scope_transient(
+            || Node::from(create_serialized_signal("count", 1).get()),
+            |_| {},
+        );
+        assert_eq!(html.to_html(), "2");
+    }
 
-        if let Some(callback) = callback {
-            callback(&node);
-        }
+    #[test]
+    fn map_and_set_helpers_notify_only_on_change() {
+        let _guard = TEST_MUTEX.lock();
+        reset_runtime_state();
 
-        node
-    });
+        let renders = Arc::new(AtomicUsize::new(0));
+        let renders_clone = renders.clone();
+        let ((scores, tags), _) = in_root_scope(move || {
+            renders_clone.fetch_add(1, Ordering::SeqCst);
+            let scores = create_signal(BTreeMap::<String, u32>::new());
+            let tags = create_signal(BTreeSet::<u32>::new());
+            let _ = (scores.len(), tags.len());
+            (scores, tags)
+        });
+        let renders = || renders.load(Ordering::SeqCst);
+
+        assert_eq!(scores.insert("ada".to_string(), 1), None);
+        assert_eq!(renders(), 2);
+        assert_eq!(scores.insert("ada".to_string(), 1), Some(1));
+        assert_eq!(scores.remove(&"grace".to_string()), None);
+        scores.update_entry("ada".to_string(), || 0, |score| *score = 1);
+        assert_eq!(renders(), 2);
+
+        scores.update_entry("ada".to_string(), || 0, |score| *score += 1);
+        assert_eq!(scores.get_value(&"ada".to_string()), Some(2));
+        assert_eq!(renders(), 3);
+
+        assert!(tags.insert(7));
+        assert!(!tags.insert(7));
+        assert!(!tags.remove(&8));
+        assert_eq!(renders(), 4);
+        assert!(tags.contains(&7));
+    }
 
-    run_scope_effects(scope_id);
+    #[test]
+    fn render_loops_are_reported_to_the_error_hook() {
+        let _guard = TEST_MUTEX.lock();
+        reset_runtime_state();
 
-    {
-        let mut rt = RUNTIME.lock();
-        if let Some(scope) = rt.scope_mut(scope_id) {
-            scope.signal_count = 0;
-            scope.effect_count = 0;
-        }
-        rt.rendering_scope = 0;
+        let reported = Arc::new(spin::Mutex::new(Vec::new()));
+        let captured = reported.clone();
+        set_error_hook(move |error| captured.lock().push(error.clone()));
+        set_max_render_iterations(10);
 
-        if !rt.scope_signal_changes.is_empty() {
-            let changed: Vec<_> = rt.scope_signal_changes.iter().copied().collect();
-            let mut scopes_to_queue = Vec::new();
-            for signal_id in changed {
-                if let Some(dependent_scopes) = rt.signal_dependencies.get(&signal_id) {
-                    for &dep_scope in dependent_scopes {
-                        if dep_scope != scope_id {
-                            scopes_to_queue.push(dep_scope);
-                        }
-                    }
+        let (ping, _) = in_root_scope(move || {
+            let ping = create_signal(0);
+            let pong = create_signal(0);
+            run_scope(
+                move || {
+                    let value = ping.get();
+                    create_effect(move || pong.set(value + 1));
+                    Node::Empty
+                },
+                |_| {},
+            );
+            run_scope(
+                move || {
+                    let value = pong.get();
+                    create_effect(move || ping.set(value + 1));
+                    Node::Empty
+                },
+                |_| {},
+            );
+            ping
+        });
+        ping.set(100);
+
+        *ERROR_HOOK.lock() = None;
+        set_max_render_iterations(100);
+
+        assert_eq!(
+            *reported.lock(),
+            vec![ReactiveError::RenderLoop {
+                iterations: 10,
+                scopes: vec![2, 3],
+                signals: vec![(1, 1), (1, 2)],
+            }]
+        );
+        assert!(runtime().lock().pending_scope_renders.is_empty());
+    }
+
+    #[cfg(any(feature = "computed", feature = "full-reactivity"))]
+    #[test]
+    fn derived_values_that_keep_changing_are_reported_to_the_error_hook() {
+        let _guard = TEST_MUTEX.lock();
+        reset_runtime_state();
+
+        let reported = Arc::new(spin::Mutex::new(Vec::new()));
+        let captured = reported.clone();
+        set_error_hook(move |error| captured.lock().push(error.clone()));
+
+        let (count, _) = in_root_scope(move || {
+            let count = create_signal(0);
+            // Invalidates itself every time it runs, as if it wrote to
+            // its own source.
+            let next = create_computed(move || {
+                let value = count.get();
+                runtime().lock().mark_observers_stale(count.id);
+                value
+            });
+            let _ = next.get();
+            count
+        });
+        count.set(1);
+
+        *ERROR_HOOK.lock() = None;
+
+        // Once for the flush after `set`, and again for the one after the
+        // scope re-rendered.
+        let reported = reported.lock();
+        assert!(!reported.is_empty());
+        for error in reported.iter() {
+            assert_eq!(
+                *error,
+                ReactiveError::FlushLoop {
+                    iterations: MAX_FLUSH_ITERATIONS,
+                    signals: vec![(1, 2)],
                 }
-            }
-            for dep_scope in scopes_to_queue {
-                rt.push_pending_render(dep_scope);
-            }
+            );
         }
+        assert!(runtime().lock().stale_nodes.is_empty());
     }
 
-    node.unwrap_or(Node::Empty)
-}
+    #[test]
+    fn entered_runtime_is_isolated_from_the_global_runtime() {
+        let _guard = TEST_MUTEX.lock();
+        reset_runtime_state();
 
-fn run_scope_effects(scope_id: usize) {
-    let effect_count = {
-        let rt = RUNTIME.lock();
-        rt.scope(scope_id).map(|s| s.effect_count).unwrap_or(0)
-    };
+        let runtime = Runtime::new();
+        let (signal, _) = runtime.enter(|| in_root_scope(|| create_signal(5)));
 
-    if effect_count == 0 {
-        return;
+        assert!(GLOBAL_RUNTIME.state.lock().scopes.is_empty());
+        assert_eq!(signal.with(|v| *v), None);
+
+        runtime.enter(|| {
+            signal.set(6);
+            assert_eq!(signal.get(), 6);
+        });
     }
 
-    for idx in 0..effect_count {
-        {
-            let mut rt = RUNTIME.lock();
-            let is_executing = rt
-                .scope(scope_id)
-                .and_then(|s| s.effect_executing.get(idx).copied())
-                .unwrap_or(false);
-            if is_executing {
-                continue;
-            }
-            if let Some(scope) = rt.scope_mut(scope_id) {
-                if idx < scope.effect_executing.len() {
-                    scope.effect_executing[idx] = true;
-                }
-            }
-            rt.executing_effects_count += 1;
-        }
+    #[test]
+    fn entering_a_second_runtime_nests_only_with_std() {
+        let _guard = TEST_MUTEX.lock();
+        reset_runtime_state();
 
-        let effect_fn = {
-            let mut rt = RUNTIME.lock();
-            rt.scope_mut(scope_id)
-                .and_then(|s| s.effects.get_mut(idx).and_then(|e| e.take()))
+        let outer = Runtime::new();
+        let inner = Runtime::new();
+        let is_current = |runtime: &Runtime| {
+            Runtime::current().is_some_and(|r| Arc::ptr_eq(&r.cell, &runtime.cell))
         };
-        if let Some(effect) = &effect_fn {
-            effect();
-        }
-        if let Some(effect) = effect_fn {
-            let mut rt = RUNTIME.lock();
-            if let Some(scope) = rt.scope_mut(scope_id) {
-                if idx < scope.effects.len() {
-                    scope.effects[idx] = Some(effect);
-                }
-            }
-        }
+        outer.enter(|| {
+            assert_eq!(outer.try_enter(|| is_current(&outer)), Ok(true));
+            #[cfg(feature = "std")]
+            assert_eq!(inner.try_enter(|| is_current(&inner)), Ok(true));
+            #[cfg(not(feature = "std"))]
+            assert_eq!(inner.try_enter(|| ()), Err(EnterError));
+            assert!(is_current(&outer));
+        });
+        assert!(Runtime::current().is_none());
+        assert_eq!(inner.try_enter(|| is_current(&inner)), Ok(true));
+    }
 
-        {
-            let mut rt = RUNTIME.lock();
-            if let Some(scope) = rt.scope_mut(scope_id) {
-                if idx < scope.effect_executing.len() {
-                    scope.effect_executing[idx] = false;
-                }
-            }
-            rt.executing_effects_count -= 1;
-        }
+    #[test]
+    fn locked_state_keeps_its_runtime_alive() {
+        let _guard = TEST_MUTEX.lock();
+        reset_runtime_state();
+
+        // The runtime is dropped when `enter` returns, but the guard owns it.
+        let mut state = Runtime::new().enter(|| runtime().lock());
+        state.batch_depth += 1;
+        assert_eq!(state.batch_depth, 1);
+        drop(state);
+        assert_eq!(runtime().lock().batch_depth, 0);
     }
-}
 
-fn process_pending_renders() {
-    const MAX_ITERATIONS: usize = 100;
-    let mut iterations = 0;
+    #[test]
+    fn isolated_runtime_clears_global_state() {
+        let _guard = TEST_MUTEX.lock();
+        reset_runtime_state();
 
-    while let Some(scope_id) = {
-        let mut rt = RUNTIME.lock();
-        if rt.pending_scope_renders.is_empty() {
-            None
-        } else {
-            // Process smallest scope_id first so parent scopes render
-            // before orphaned child scopes (matches main branch BTreeSet order).
-            let min_idx = rt
-                .pending_scope_renders
-                .iter()
-                .enumerate()
-                .min_by_key(|&(_, &id)| id)
-                .map(|(i, _)| i)
-                .unwrap();
-            Some(rt.pending_scope_renders.swap_remove(min_idx))
-        }
-    } {
-        iterations += 1;
-        if iterations >= MAX_ITERATIONS {
-            break;
-        }
-        render_scope(scope_id);
+        let html = with_isolated_runtime(|| {
+            run_scope_transient(
+                || {
+                    let signal = create_signal(7);
+                    Node::from(signal.get())
+                },
+                |_| {},
+            )
+            .to_html()
+        });
+
+        assert_eq!(html, "7");
+        let rt = runtime().lock();
+        assert!(rt.scopes.iter().all(|s| s.is_none()));
+        assert!(rt.scope_dependencies.is_empty());
+        assert!(rt.signal_dependencies.is_empty());
     }
-}
-
-#[cfg(test)]
-fn reset_runtime_state() {
-    let mut rt = RUNTIME.lock();
-    *rt = RuntimeState::new();
-}
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
-pub enum ResourceStatus {
-    Idle,
-    Pending,
-    Loading,
-    Resolved,
-}
+    #[test]
+    fn fallible_accessors_report_why_a_signal_is_unusable() {
+        let _guard = TEST_MUTEX.lock();
+        reset_runtime_state();
 
-impl SignalValue for ResourceStatus {
-    fn as_any(&self) -> Option<&dyn Any> {
-        Some(self)
-    }
-    fn as_any_mut(&mut self) -> Option<&mut dyn Any> {
-        Some(self)
-    }
-}
+        assert_eq!(
+            try_create_signal(0).map(|_: Signal<i32>| ()),
+            Err(SignalError::OutsideScope)
+        );
 
-pub struct Resource<T> {
-    status: Signal<ResourceStatus>,
-    value: Signal<Option<T>>,
-}
+        let ((count, line), scope) = in_root_scope(move || (create_signal(1), line!()));
+        assert_eq!(count.try_get(), Ok(1));
 
-impl<T: SignalValue + PartialEq + 'static> Resource<T> {
-    pub fn status(&self) -> Signal<ResourceStatus> {
-        self.status
-    }
+        let wrong = Signal::<&str> {
+            id: count.id,
+            generation: count.generation,
+            #[cfg(debug_assertions)]
+            created_at: count.created_at,
+            _marker: PhantomData,
+        };
+        assert_eq!(
+            wrong.try_get(),
+            Err(SignalError::TypeMismatch {
+                signal: count.id,
+                expected: "&str",
+                created_at: count.created_at(),
+            })
+        );
 
-    pub fn get(&self) -> Option<T>
-    where
-        T: Clone,
-    {
-        self.value.get()
+        scope.dispose();
+        let disposed = Err(SignalError::Disposed {
+            signal: count.id,
+            created_at: count.created_at(),
+        });
+        assert_eq!(count.try_get(), disposed);
+        assert_eq!(count.try_set(2), disposed.map(|_| ()));
+        assert_eq!(count.with(|value| *value), None);
+        count.set(2);
+
+        // A new scope reusing the id does not revive the old signal.
+        let (fresh, _) = in_root_scope(|| create_signal(7));
+        assert_eq!(fresh.id, count.id);
+        assert_eq!(fresh.try_get(), Ok(7));
+        assert_eq!(count.try_get(), disposed);
+        assert_eq!(
+            SignalError::Disposed {
+                signal: count.id,
+                created_at: None,
+            }
+            .to_string(),
+            "Signal 1 of scope 1 was used after its scope was disposed"
+        );
+        // Debug builds point at the line that created the signal.
+        #[cfg(debug_assertions)]
+        assert!(
+            count
+                .try_get()
+                .unwrap_err()
+                .to_string()
+                .ends_with(&alloc::format!(
+                    "disposed (created at {}:{line}:{})",
+                    file!(),
+                    count.created_at.column()
+                ))
+        );
     }
 
-    pub fn with<R>(&self, f: impl FnOnce(&T) -> R) -> Option<R> {
-        self.value.with(|v| v.as_ref().map(f)).unwrap_or_default()
-    }
+    #[test]
+    fn custom_equality_and_in_place_updates_control_notifications() {
+        let _guard = TEST_MUTEX.lock();
+        reset_runtime_state();
 
-    pub fn retry(&self) {
-        self.status.set(ResourceStatus::Pending);
-    }
-}
+        /// Compared by revision only, and without `PartialEq`.
+        struct Document {
+            revision: u32,
+            lines: Vec<&'static str>,
+        }
 
-#[allow(unused_variables)]
-/// Create a resource that can be asynchronously loaded
-pub fn create_resource<T, F>(fetcher: F) -> Resource<T>
-where
-    T: SignalValue + PartialEq + 'static,
-    F: AsyncFn() -> T + Send + Clone + 'static,
-{
-    let value = create_signal(None);
-    let status = create_signal(ResourceStatus::Idle);
+        let renders = Arc::new(AtomicUsize::new(0));
+        let renders_clone = renders.clone();
+        let ((document, tags), _) = in_root_scope(move || {
+            renders_clone.fetch_add(1, Ordering::SeqCst);
+            let document = create_signal_with_eq(
+                Document {
+                    revision: 1,
+                    lines: Vec::new(),
+                },
+                |a: &Document, b: &Document| a.revision == b.revision,
+            );
+            let tags = create_signal(vec!["draft"]);
+            let _ = (document.with(|d| d.lines.len()), tags.len());
+            (document, tags)
+        });
+        let renders = || renders.load(Ordering::SeqCst);
 
-    create_effect(move || {
-        if status.get() == ResourceStatus::Idle || status.get() == ResourceStatus::Pending {
-            #[cfg(all(feature = "wasm", target_arch = "wasm32"))]
-            status.set(ResourceStatus::Loading);
+        document.set(Document {
+            revision: 1,
+            lines: vec!["ignored"],
+        });
+        assert_eq!(renders(), 1);
+        assert_eq!(document.with(|d| d.lines.len()), Some(0));
 
-            #[cfg(all(feature = "wasm", target_arch = "wasm32"))]
-            let fetcher = fetcher.clone();
+        document.update(|d| d.lines.push("intro"));
+        assert_eq!(renders(), 2);
+        assert_eq!(document.with(|d| d.lines.clone()), Some(vec!["intro"]));
 
-            #[cfg(all(feature = "wasm", target_arch = "wasm32"))]
-            wasm_bindgen_futures::spawn_local(async move {
-                let val = fetcher().await;
-                value.set(Some(val));
-                status.set(ResourceStatus::Resolved);
-            });
+        assert!(!tags.update_if_changed(|t| t.sort(), |a, b| a == b));
+        assert_eq!(renders(), 2);
+        assert!(tags.update_if_changed(|t| t.push("rust"), |a, b| a == b));
+        assert_eq!(renders(), 3);
+    }
 
-            #[cfg(not(all(feature = "wasm", target_arch = "wasm32")))]
-            let _ = &fetcher;
-        }
-    });
+    #[test]
+    fn updates_and_equality_checks_run_without_the_runtime_lock() {
+        let _guard = TEST_MUTEX.lock();
+        reset_runtime_state();
 
-    Resource { status, value }
-}
+        let limit = root_signal(10);
+        let count = root_signal(0);
+        count.update(|n| *n = limit.get());
+        assert_eq!(count.get(), 10);
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use alloc::sync::Arc;
-    use core::sync::atomic::{AtomicUsize, Ordering};
+        let mut seen = None;
+        count.update(|_| seen = Some(count.try_get()));
+        let in_use = SignalError::InUse {
+            signal: count.id,
+            created_at: count.created_at(),
+        };
+        assert_eq!(seen, Some(Err(in_use)));
+        assert_eq!(count.try_get(), Ok(10));
 
-    static TEST_MUTEX: spin::Mutex<()> = spin::Mutex::new(());
+        let (reading, _) = in_root_scope(move || {
+            let within_limit = move |a: &i32, b: &i32| (a - b).abs() < limit.get();
+            create_signal_with_eq(0, within_limit)
+        });
+        reading.set(3);
+        assert_eq!(reading.get(), 0);
+        reading.set(30);
+        assert_eq!(reading.get(), 30);
+    }
 
     #[test]
-    fn test_nested_scopes() {
+    fn failed_transactions_roll_back_map_and_set_helpers() {
         let _guard = TEST_MUTEX.lock();
         reset_runtime_state();
 
-        run_scope(
-            || {
-                let outer_signal = create_signal(0);
+        let stock = root_signal(BTreeMap::from([("apples", 3), ("pears", 0), ("plums", 5)]));
+        let tags = root_signal(BTreeSet::from(["fruit", "sale"]));
+        let before = (stock.get(), tags.get());
+
+        let result = transaction(|| {
+            stock.insert("apples", 4);
+            stock.insert("kiwis", 1);
+            stock.remove(&"plums");
+            stock.update_entry("pears", || 0, |count| *count += 2);
+            stock.update_entry("limes", || 0, |count| *count += 1);
+            stock.retain(|_, count| {
+                *count *= 10;
+                *count > 10
+            });
+            tags.insert("fresh");
+            tags.retain(|tag| *tag != "sale");
+            Err::<(), _>("sync failed")
+        });
 
-                run_scope(
-                    move || {
-                        let inner_signal = create_signal("hello");
-                        assert!(inner_signal.get() == "hello");
-                        outer_signal.set(42);
-                        Node::Empty
-                    },
-                    |_| {},
-                );
+        assert_eq!(result, Err("sync failed"));
+        assert_eq!((stock.get(), tags.get()), before);
+        assert!(runtime().lock().transactions.is_empty());
+    }
 
-                assert_eq!(outer_signal.get(), 42);
-                Node::Empty
-            },
-            |_| {},
+    #[test]
+    fn failed_transactions_report_updates_they_cannot_undo() {
+        let _guard = TEST_MUTEX.lock();
+        reset_runtime_state();
+
+        let reported = Arc::new(spin::Mutex::new(Vec::new()));
+        let captured = reported.clone();
+        set_error_hook(move |error| captured.lock().push(error.clone()));
+
+        let saved = root_signal(0);
+        let log = root_signal(vec!["start"]);
+        let tags = root_signal(BTreeSet::from(["draft"]));
+        let result = transaction(|| {
+            saved.set(1);
+            log.update(|lines| lines.push("saving"));
+            tags.update(|tags| {
+                tags.insert("saved");
+            });
+            Err::<(), _>("offline")
+        });
+        *ERROR_HOOK.lock() = None;
+
+        assert_eq!(result, Err("offline"));
+        assert_eq!(saved.get(), 0);
+        assert_eq!(log.get(), ["start", "saving"]);
+        assert_eq!(tags.len(), 2);
+        assert_eq!(
+            *reported.lock(),
+            [ReactiveError::PartialRollback {
+                signals: vec![log.id, tags.id]
+            }]
         );
     }
 
+    /// Create a signal in a fresh root scope that outlives the render.
+    fn root_signal<T: SignalValue + PartialEq>(init: T) -> Signal<T> {
+        let mut init = Some(init);
+        in_root_scope(move || create_signal(init.take().expect("the scope renders once"))).0
+    }
+
     #[test]
-    fn test_signal_and_effect_in_scope() {
+    fn tuples_can_be_stored_without_a_derive() {
         let _guard = TEST_MUTEX.lock();
         reset_runtime_state();
 
-        run_scope(
-            move || {
-                let effect_count = Arc::new(AtomicUsize::new(0));
-                let effect_count_clone = effect_count.clone();
-                let signal = create_signal(0);
+        let tuple = root_signal((1u8, "one".to_string(), vec![true]));
+        tuple.update(|(n, _, flags)| {
+            *n += 1;
+            flags.push(false);
+        });
+        assert_eq!(tuple.get(), (2, "one".to_string(), vec![true, false]));
+    }
 
-                create_effect(move || {
-                    let _ = signal.get();
-                    effect_count_clone.fetch_add(1, Ordering::SeqCst);
-                    assert!(effect_count.load(Ordering::SeqCst) > 0);
-                    signal.set(1);
-                });
+    #[test]
+    fn arrays_can_be_stored_without_a_derive() {
+        let _guard = TEST_MUTEX.lock();
+        reset_runtime_state();
 
-                Node::Empty
-            },
-            |_| {},
-        );
+        let array = root_signal([1u32, 2, 3]);
+        array.update(|a| a[0] = 9);
+        assert_eq!(array.get(), [9, 2, 3]);
     }
 
     #[test]
-    fn test_multiple_signals_and_dependencies() {
+    fn strings_can_be_stored_without_a_derive() {
         let _guard = TEST_MUTEX.lock();
         reset_runtime_state();
 
-        run_scope(
-            || {
-                let signal1 = create_signal("hello");
-                let signal2 = create_signal(0);
-
-                create_effect(move || {
-                    let _str_val = signal1.get();
-                    let _num_val = signal2.get();
-                });
+        let string = root_signal("text".to_string());
+        string.update(|s| s.push('!'));
+        assert_eq!(string.get(), "text!");
+        assert_eq!(string.try_with(String::len), Ok(5));
+    }
 
-                signal1.set("world");
-                signal2.set(42);
+    #[test]
+    fn boxes_can_be_stored_without_a_derive() {
+        let _guard = TEST_MUTEX.lock();
+        reset_runtime_state();
 
-                assert_eq!(signal1.get(), "world");
-                assert_eq!(signal2.get(), 42);
+        // The signal's own storage is boxed too; reads must reach the
+        // user's box, not the one wrapping it.
+        let boxed = root_signal(Box::new(7u32));
+        boxed.set(Box::new(8));
+        assert_eq!(*boxed.get(), 8);
 
-                Node::Empty
-            },
-            |_| {},
+        type Erased = Box<dyn Any + Send>;
+        let (erased, _) = in_root_scope(|| {
+            let value: Erased = Box::new(3u32);
+            create_signal_with_eq(value, |_, _| false)
+        });
+        assert_eq!(
+            erased.with(|value| value.downcast_ref::<u32>().copied()),
+            Some(Some(3))
         );
     }
 
     #[test]
-    fn stale_effects_are_removed_after_rerender() {
+    fn arcs_can_be_stored_without_a_derive() {
         let _guard = TEST_MUTEX.lock();
         reset_runtime_state();
 
-        let handles = Arc::new(spin::Mutex::new(None));
-        let had_effect = Arc::new(AtomicUsize::new(0));
+        let shared: Arc<str> = Arc::from("shared");
+        let arc = root_signal(shared.clone());
+        assert!(Arc::ptr_eq(&arc.get(), &shared));
+        arc.set(Arc::from("replaced"));
+        assert_eq!(&*arc.get(), "replaced");
+    }
 
-        run_scope(
-            {
-                let handles = handles.clone();
-                let had_effect = had_effect.clone();
+    #[test]
+    fn user_structs_can_be_stored_without_a_derive() {
+        let _guard = TEST_MUTEX.lock();
+        reset_runtime_state();
 
-                move || {
-                    let enabled = create_signal(true);
-                    let trigger = create_signal(0);
+        #[derive(Clone, PartialEq, Debug)]
+        struct Point {
+            x: i32,
+            y: i32,
+        }
 
-                    *handles.lock() = Some((enabled, trigger));
+        let point = root_signal(Point { x: 1, y: 2 });
+        point.set(Point { x: 3, y: 4 });
+        assert_eq!(point.get(), Point { x: 3, y: 4 });
+    }
 
-                    if enabled.get() {
-                        create_effect(move || {
-                            let _ = trigger.get();
-                        });
+    #[test]
+    fn effect_phases_run_around_the_patch_children_first() {
+        let _guard = TEST_MUTEX.lock();
+        reset_runtime_state();
 
-                        had_effect.store(1, Ordering::SeqCst);
-                    }
+        type Log = Arc<spin::Mutex<Vec<String>>>;
+        fn effects(log: &Log, scope: &'static str) {
+            let record = |phase: &'static str| {
+                let log = log.clone();
+                move || log.lock().push(alloc::format!("{scope} {phase}"))
+            };
+            create_effect(record("passive"));
+            create_layout_effect(record("layout"));
+            create_render_effect(record("render"));
+        }
 
-                    Node::Empty
-                }
+        let log: Log = Arc::new(spin::Mutex::new(Vec::new()));
+        let slot = Arc::new(spin::Mutex::new(None));
+        let (captured, scope_log, patch_log) = (slot.clone(), log.clone(), log.clone());
+        run_scope(
+            move || {
+                let count = create_signal(0);
+                *captured.lock() = Some(count);
+                count.get();
+                effects(&scope_log, "parent");
+                let (child_log, child_patch) = (scope_log.clone(), scope_log.clone());
+                run_scope(
+                    move || {
+                        effects(&child_log, "child");
+                        Node::Empty
+                    },
+                    move |_| child_patch.lock().push("child patch".to_string()),
+                )
             },
-            |_| {},
+            move |_| patch_log.lock().push("parent patch".to_string()),
         );
 
-        let (enabled, _) = handles.lock().as_ref().copied().unwrap();
-        let scope_id = enabled.id.0;
-
-        assert_eq!(had_effect.load(Ordering::SeqCst), 1);
-
-        enabled.set(false);
-        render_scope(scope_id);
-
-        assert!(
-            RUNTIME
-                .lock()
-                .scope(scope_id)
-                .map(|s| s.effects.iter().all(|e| e.is_none()))
-                .unwrap_or(true)
-        );
+        let expected = [
+            "child render",
+            "child patch",
+            "parent render",
+            "parent patch",
+            "child layout",
+            "parent layout",
+            "child passive",
+            "parent passive",
+        ];
+        assert_eq!(core::mem::take(&mut *log.lock()), expected);
+
+        let count = slot.lock().unwrap();
+        count.set(1);
+        assert_eq!(core::mem::take(&mut *log.lock()), expected);
     }
-
     #[test]
-    fn transient_scope_disposes_runtime_state() {
+    fn panicking_renders_are_closed() {
         let _guard = TEST_MUTEX.lock();
         reset_runtime_state();
 
-        run_scope_transient(
-            || {
-                let signal = create_signal(1);
+        let panicked = std::panic::catch_unwind(|| {
+            run_scope(|| panic!("component failed"), |_| {});
+        });
+        assert!(panicked.is_err());
+        {
+            let rt = runtime().lock();
+            assert_eq!((rt.render_depth, rt.rendering_scope), (0, 0));
+        }
+
+        // Effects of later renders still run once the render finishes.
+        let ran = Arc::new(AtomicUsize::new(0));
+        let counter = ran.clone();
+        run_scope(
+            move || {
+                let counter = counter.clone();
                 create_effect(move || {
-                    let _ = signal.get();
+                    counter.fetch_add(1, Ordering::SeqCst);
                 });
                 Node::Empty
             },
             |_| {},
         );
-
-        let rt = RUNTIME.lock();
-        // Transient scopes clear their data but keep the slot for reuse
-        for scope in rt.scopes.iter().flatten() {
-            assert_eq!(scope.signal_count, 0);
-            assert_eq!(scope.effect_count, 0);
-            assert!(scope.signals.is_empty());
-            assert!(scope.effects.is_empty());
-        }
-        // Dep tracking is skipped in transient mode, so these stay empty
-        assert!(rt.scope_dependencies.is_empty());
-        assert!(rt.signal_dependencies.is_empty());
+        assert_eq!(ran.load(Ordering::SeqCst), 1);
     }
 
     #[test]
-    fn non_wasm_resources_do_not_enter_loading_state() {
+    fn queued_effects_skip_scopes_disposed_before_they_run() {
         let _guard = TEST_MUTEX.lock();
         reset_runtime_state();
 
-        run_scope_transient(
-            || {
-                let resource = create_resource(|| async { 1_u32 });
-                assert_eq!(resource.status().get(), ResourceStatus::Idle);
+        let log = Arc::new(spin::Mutex::new(Vec::new()));
+        let child = |log: &Arc<spin::Mutex<Vec<&'static str>>>, name: &'static str| {
+            let log = log.clone();
+            let handle = Arc::new(spin::Mutex::new(None));
+            let captured = handle.clone();
+            run_scope(
+                move || {
+                    *captured.lock() = ScopeHandle::current();
+                    let log = log.clone();
+                    create_effect(move || log.lock().push(name));
+                    Node::Empty
+                },
+                |_| {},
+            );
+            handle.lock().unwrap()
+        };
+        let scope_log = log.clone();
+        run_scope(
+            move || {
+                let first = child(&scope_log, "first");
+                child(&scope_log, "second");
+                first.dispose();
+                // Reuses the id of `first`, but finishes after `second`.
+                child(&scope_log, "third");
                 Node::Empty
             },
             |_| {},
         );
+        assert_eq!(*log.lock(), ["second", "third"]);
     }
 
     #[test]
-    fn isolated_runtime_clears_global_state() {
+    fn transient_scopes_skip_layout_effects() {
         let _guard = TEST_MUTEX.lock();
         reset_runtime_state();
 
-        let html = with_isolated_runtime(|| {
-            run_scope_transient(
-                || {
-                    let signal = create_signal(7);
-                    Node::from(signal.get())
-                },
-                |_| {},
-            )
-            .to_html()
-        });
-
-        assert_eq!(html, "7");
-        let rt = RUNTIME.lock();
-        assert!(rt.scopes.iter().all(|s| s.is_none()));
-        assert!(rt.scope_dependencies.is_empty());
-        assert!(rt.signal_dependencies.is_empty());
+        let log = Arc::new(spin::Mutex::new(Vec::new()));
+        let scope_log = log.clone();
+        run_scope_transient(
+            move || {
+                for phase in ["render", "layout", "passive"] {
+                    let log = scope_log.clone();
+                    let effect = move || log.lock().push(phase);
+                    match phase {
+                        "render" => create_render_effect(effect),
+                        "layout" => create_layout_effect(effect),
+                        _ => create_effect(effect),
+                    }
+                }
+                Node::Empty
+            },
+            |_| {},
+        );
+        assert_eq!(*log.lock(), ["render", "passive"]);
     }
 }
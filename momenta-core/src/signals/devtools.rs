@@ -0,0 +1,283 @@
+//! Introspection of the reactive graph, for debugging and tests.
+
+use super::runtime;
+use alloc::{string::String, vec::Vec};
+
+/// A node of the reactive graph.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum GraphNode {
+    Scope(usize),
+    /// A signal, by owning scope and slot.
+    Signal(usize, usize),
+    /// An effect, by owning scope and position, counting from 1 like
+    /// signal slots. Effects re-run whenever their scope renders.
+    Effect(usize, usize),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ScopeSnapshot {
+    pub id: usize,
+    pub parent: Option<usize>,
+    pub children: Vec<usize>,
+    pub signals: usize,
+    pub effects: usize,
+    pub render_count: usize,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SignalSnapshot {
+    /// The owning scope and slot.
+    pub id: (usize, usize),
+    /// True for computed and memoized signals.
+    pub derived: bool,
+}
+
+/// A dependency: `to` is re-run or re-rendered when `from` changes. Edges
+/// into an effect are the signals it read during its last run; those
+/// reads also subscribe the effect's scope.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub struct GraphEdge {
+    pub from: GraphNode,
+    pub to: GraphNode,
+    /// Set when `to` only depends on part of the value, e.g. a store field.
+    pub keyed: bool,
+}
+
+/// The scopes, signals and dependency edges of the current runtime at one
+/// point in time. Returned by [`graph_snapshot`].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct GraphSnapshot {
+    pub scopes: Vec<ScopeSnapshot>,
+    pub signals: Vec<SignalSnapshot>,
+    pub edges: Vec<GraphEdge>,
+}
+
+impl GraphSnapshot {
+    /// Render the graph in Graphviz DOT format. Scopes are boxes labelled
+    /// with their render count, derived signals are diamonds, effects are
+    /// hexagons, ownership is drawn dashed and keyed dependencies dotted.
+    pub fn to_dot(&self) -> String {
+        use core::fmt::Write;
+
+        fn name(node: GraphNode) -> String {
+            match node {
+                GraphNode::Scope(id) => alloc::format!("scope_{id}"),
+                GraphNode::Signal(scope, slot) => alloc::format!("signal_{scope}_{slot}"),
+                GraphNode::Effect(scope, idx) => alloc::format!("effect_{scope}_{idx}"),
+            }
+        }
+
+        let mut dot = String::from("digraph momenta {\n");
+        for scope in &self.scopes {
+            let _ = writeln!(
+                dot,
+                "    {} [shape=box, label=\"scope {}\\nrenders: {}, effects: {}\"];",
+                name(GraphNode::Scope(scope.id)),
+                scope.id,
+                scope.render_count,
+                scope.effects
+            );
+            if let Some(parent) = scope.parent {
+                let _ = writeln!(
+                    dot,
+                    "    {} -> {} [style=dashed];",
+                    name(GraphNode::Scope(parent)),
+                    name(GraphNode::Scope(scope.id))
+                );
+            }
+            for idx in 1..=scope.effects {
+                let node = GraphNode::Effect(scope.id, idx);
+                let _ = writeln!(
+                    dot,
+                    "    {} [shape=hexagon, label=\"effect {}:{idx}\"];",
+                    name(node),
+                    scope.id
+                );
+                let _ = writeln!(
+                    dot,
+                    "    {} -> {} [style=dashed, arrowhead=none];",
+                    name(GraphNode::Scope(scope.id)),
+                    name(node)
+                );
+            }
+        }
+        for signal in &self.signals {
+            let node = GraphNode::Signal(signal.id.0, signal.id.1);
+            let (shape, kind) = if signal.derived {
+                ("diamond", "derived")
+            } else {
+                ("ellipse", "signal")
+            };
+            let _ = writeln!(
+                dot,
+                "    {} [shape={shape}, label=\"{kind} {}:{}\"];",
+                name(node),
+                signal.id.0,
+                signal.id.1
+            );
+            let _ = writeln!(
+                dot,
+                "    {} -> {} [style=dashed, arrowhead=none];",
+                name(GraphNode::Scope(signal.id.0)),
+                name(node)
+            );
+        }
+        for edge in &self.edges {
+            let style = if edge.keyed { " [style=dotted]" } else { "" };
+            let _ = writeln!(dot, "    {} -> {}{style};", name(edge.from), name(edge.to));
+        }
+        dot.push_str("}\n");
+        dot
+    }
+}
+
+/// Capture the reactive graph of the current runtime.
+///
+/// # Example
+/// ```ignore
+/// use momenta_core::signals::graph_snapshot;
+///
+/// let graph = graph_snapshot();
+/// std::fs::write("graph.dot", graph.to_dot()).unwrap();
+/// ```
+pub fn graph_snapshot() -> GraphSnapshot {
+    let rt = runtime().lock();
+    let mut snapshot = GraphSnapshot::default();
+
+    for (idx, scope) in rt.scopes.iter().enumerate() {
+        let Some(scope) = scope else {
+            continue;
+        };
+        let id = idx + 1;
+        snapshot.scopes.push(ScopeSnapshot {
+            id,
+            parent: scope.parent,
+            children: scope.children.clone(),
+            signals: scope.signals.len(),
+            effects: scope.effects.len(),
+            render_count: scope.render_count,
+        });
+        for (idx, reads) in scope.effect_reads.iter().enumerate() {
+            snapshot.edges.extend(reads.iter().map(|&signal| GraphEdge {
+                from: GraphNode::Signal(signal.0, signal.1),
+                to: GraphNode::Effect(id, idx + 1),
+                keyed: false,
+            }));
+        }
+        for (slot, stored) in scope.signals.iter().enumerate() {
+            let signal = (id, slot + 1);
+            snapshot.signals.push(SignalSnapshot {
+                id: signal,
+                derived: stored.derived.is_some(),
+            });
+            snapshot
+                .edges
+                .extend(stored.observers.iter().map(|&observer| GraphEdge {
+                    from: GraphNode::Signal(signal.0, signal.1),
+                    to: GraphNode::Signal(observer.0, observer.1),
+                    keyed: false,
+                }));
+        }
+    }
+
+    for (signal, scopes) in &rt.signal_dependencies {
+        snapshot.edges.extend(scopes.iter().map(|&scope| GraphEdge {
+            from: GraphNode::Signal(signal.0, signal.1),
+            to: GraphNode::Scope(scope),
+            keyed: false,
+        }));
+    }
+    for (signal, deps) in &rt.keyed_dependencies {
+        for &(_, scope) in deps {
+            let edge = GraphEdge {
+                from: GraphNode::Signal(signal.0, signal.1),
+                to: GraphNode::Scope(scope),
+                keyed: true,
+            };
+            if !snapshot.edges.contains(&edge) {
+                snapshot.edges.push(edge);
+            }
+        }
+    }
+    snapshot.edges.sort();
+    snapshot
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::signals::{
+        TEST_MUTEX, create_computed, create_effect, create_signal, in_root_scope,
+        reset_runtime_state,
+    };
+    use alloc::vec;
+
+    #[test]
+    fn graph_snapshot_reports_edges_and_render_counts() {
+        let _guard = TEST_MUTEX.lock();
+        reset_runtime_state();
+
+        let (count, _) = in_root_scope(move || {
+            let count = create_signal(0);
+            let doubled = create_computed(move || count.get() * 2);
+            create_effect(move || {
+                let _ = count.get();
+            });
+            let _ = doubled.get();
+            count
+        });
+        count.set(1);
+
+        let graph = graph_snapshot();
+        assert_eq!(graph.scopes.len(), 1);
+        assert_eq!(graph.scopes[0].render_count, 2);
+        assert_eq!(graph.scopes[0].effects, 1);
+        assert_eq!(
+            graph.signals,
+            vec![
+                SignalSnapshot {
+                    id: (1, 1),
+                    derived: false
+                },
+                SignalSnapshot {
+                    id: (1, 2),
+                    derived: true
+                },
+            ]
+        );
+        assert_eq!(
+            graph.edges,
+            vec![
+                GraphEdge {
+                    from: GraphNode::Signal(1, 1),
+                    to: GraphNode::Scope(1),
+                    keyed: false,
+                },
+                GraphEdge {
+                    from: GraphNode::Signal(1, 1),
+                    to: GraphNode::Signal(1, 2),
+                    keyed: false,
+                },
+                GraphEdge {
+                    from: GraphNode::Signal(1, 1),
+                    to: GraphNode::Effect(1, 1),
+                    keyed: false,
+                },
+                GraphEdge {
+                    from: GraphNode::Signal(1, 2),
+                    to: GraphNode::Scope(1),
+                    keyed: false,
+                },
+            ]
+        );
+
+        let dot = graph.to_dot();
+        assert!(dot.starts_with("digraph momenta {\n"));
+        assert!(dot.contains("scope_1 [shape=box, label=\"scope 1\\nrenders: 2, effects: 1\"];"));
+        assert!(dot.contains("effect_1_1 [shape=hexagon, label=\"effect 1:1\"];"));
+        assert!(dot.contains("    signal_1_1 -> effect_1_1;\n"));
+        assert!(dot.contains("signal_1_2 [shape=diamond, label=\"derived 1:2\"];"));
+        assert!(dot.contains("    signal_1_1 -> signal_1_2;\n"));
+        assert!(dot.contains("    signal_1_2 -> scope_1;\n"));
+    }
+}
@@ -0,0 +1,321 @@
+//! Undo/redo history of a signal.
+
+use super::{
+    Signal, SignalValue, batch, create_signal, create_watcher, timers::installed_timer, untrack,
+};
+use alloc::{collections::VecDeque, vec::Vec};
+
+/// Undo/redo history of a signal, created with [`create_history`].
+///
+/// Every change to the signal, however it was made, becomes an entry once it
+/// has been applied, so all changes made in one [`batch`] form a single entry.
+/// Recording does not re-render the scope that owns the history.
+#[derive(Debug)]
+pub struct History<T> {
+    source: Signal<T>,
+    state: Signal<HistoryState<T>>,
+    can_undo: Signal<bool>,
+    can_redo: Signal<bool>,
+}
+
+impl<T> Copy for History<T> {}
+
+impl<T> Clone for History<T> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct HistoryState<T> {
+    past: VecDeque<T>,
+    /// The value the entries lead up to; changes are recorded against it.
+    current: T,
+    future: Vec<T>,
+    capacity: usize,
+    coalesce_ms: Option<u32>,
+    /// When the newest entry was started, while changes may still be merged
+    /// into it.
+    entry_started: Option<u64>,
+}
+
+impl<T: SignalValue + PartialEq + Clone + 'static> History<T> {
+    /// Put back the value from before the last change. Does nothing if there
+    /// is nothing to undo.
+    pub fn undo(&self) {
+        self.step(|state| {
+            let previous = state.past.pop_back()?;
+            let current = core::mem::replace(&mut state.current, previous.clone());
+            state.future.push(current);
+            Some(previous)
+        });
+    }
+
+    /// Reapply the last undone change. Does nothing if there is nothing to
+    /// redo; any new change clears what could be redone.
+    pub fn redo(&self) {
+        self.step(|state| {
+            let next = state.future.pop()?;
+            let current = core::mem::replace(&mut state.current, next.clone());
+            state.past.push_back(current);
+            Some(next)
+        });
+    }
+
+    /// True while there is something to undo.
+    pub fn can_undo(&self) -> Signal<bool> {
+        self.can_undo
+    }
+
+    /// True while there is something to redo.
+    pub fn can_redo(&self) -> Signal<bool> {
+        self.can_redo
+    }
+
+    /// Forget every entry, keeping the current value.
+    pub fn clear(&self) {
+        batch(|| {
+            self.state.modify(|state| {
+                let changed = !state.past.is_empty() || !state.future.is_empty();
+                state.past.clear();
+                state.future.clear();
+                state.entry_started = None;
+                ((), changed)
+            });
+            self.sync_flags();
+        });
+    }
+
+    /// Move through the entries with `f` and set the signal to the value it
+    /// returns, notifying dependents once.
+    fn step(&self, f: impl FnOnce(&mut HistoryState<T>) -> Option<T>) {
+        batch(|| {
+            let value = self
+                .state
+                .modify(|state| {
+                    let value = f(state);
+                    state.entry_started = None;
+                    let changed = value.is_some();
+                    (value, changed)
+                })
+                .flatten();
+            if let Some(value) = value {
+                self.source.set(value);
+                self.sync_flags();
+            }
+        });
+    }
+
+    fn record(&self, value: T) {
+        let now = installed_timer().map(|timer| timer.now());
+        let changed = self.state.modify(|state| {
+            if state.current == value {
+                return (false, false);
+            }
+            let coalesce = match (state.coalesce_ms, state.entry_started, now) {
+                (Some(window), Some(started), Some(now)) => {
+                    now.saturating_sub(started) < u64::from(window)
+                }
+                _ => false,
+            };
+            let previous = core::mem::replace(&mut state.current, value);
+            if !coalesce {
+                state.past.push_back(previous);
+                if state.past.len() > state.capacity {
+                    state.past.pop_front();
+                }
+                state.entry_started = now;
+            }
+            state.future.clear();
+            (true, true)
+        });
+        if changed == Some(true) {
+            self.sync_flags();
+        }
+    }
+
+    fn sync_flags(&self) {
+        let (undo, redo) = self
+            .state
+            .with_untracked(|state| (!state.past.is_empty(), !state.future.is_empty()))
+            .unwrap_or_default();
+        self.can_undo.set(undo);
+        self.can_redo.set(redo);
+    }
+}
+
+/// Record the changes to `signal` so they can be undone and redone, keeping
+/// at most `capacity` entries.
+///
+/// # Example
+/// ```ignore
+/// use momenta::prelude::*;
+///
+/// let text = create_signal(String::new());
+/// let history = create_history(text, 100);
+///
+/// rsx! {
+///     <button on:click={move |_| history.undo()} disabled={!history.can_undo().get()}>
+///         "Undo"
+///     </button>
+/// }
+/// ```
+pub fn create_history<T>(signal: Signal<T>, capacity: usize) -> History<T>
+where
+    T: SignalValue + PartialEq + Clone + 'static,
+{
+    create_history_inner(signal, capacity, None)
+}
+
+/// Like [`create_history`], but changes made within `window_ms` milliseconds
+/// of the first change of an entry are merged into it, so that e.g. a burst of
+/// keystrokes is undone at once. A longer burst still starts a new entry every
+/// `window_ms`. Time is read from the installed [`Timer`](super::Timer);
+/// without one nothing is merged.
+pub fn create_history_with_coalescing<T>(
+    signal: Signal<T>,
+    capacity: usize,
+    window_ms: u32,
+) -> History<T>
+where
+    T: SignalValue + PartialEq + Clone + 'static,
+{
+    create_history_inner(signal, capacity, Some(window_ms))
+}
+
+fn create_history_inner<T>(
+    source: Signal<T>,
+    capacity: usize,
+    coalesce_ms: Option<u32>,
+) -> History<T>
+where
+    T: SignalValue + PartialEq + Clone + 'static,
+{
+    let history = History {
+        source,
+        state: create_signal(HistoryState {
+            past: VecDeque::new(),
+            current: source.get_untracked(),
+            future: Vec::new(),
+            capacity,
+            coalesce_ms,
+            entry_started: None,
+        }),
+        can_undo: create_signal(false),
+        can_redo: create_signal(false),
+    };
+
+    // Runs after every change, before the scopes that read `can_undo` or
+    // `can_redo` re-render.
+    create_watcher(move || {
+        if let Some(value) = source.with(T::clone) {
+            untrack(|| history.record(value));
+        }
+    });
+    history
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::signals::{
+        TEST_MUTEX, VirtualClock, in_root_scope, reset_runtime_state, set_timer, timers::TIMER,
+    };
+    use alloc::sync::Arc;
+    use core::sync::atomic::{AtomicUsize, Ordering};
+
+    #[test]
+    fn history_undoes_and_redoes_changes() {
+        let _guard = TEST_MUTEX.lock();
+        reset_runtime_state();
+
+        let ((text, history), _) = in_root_scope(|| {
+            let text = create_signal("a");
+            let history = create_history(text, 2);
+            let _ = (text.get(), history.can_undo().get());
+            (text, history)
+        });
+        assert!(!history.can_undo().get());
+
+        text.set("ab");
+        batch(|| {
+            text.set("abc");
+            text.set("abcd");
+        });
+        text.set("abcde");
+        assert!(history.can_undo().get());
+
+        // Only two entries are kept.
+        history.undo();
+        history.undo();
+        assert_eq!(text.get(), "ab");
+        assert!(!history.can_undo().get());
+        history.undo();
+        assert_eq!(text.get(), "ab");
+
+        history.redo();
+        assert_eq!(text.get(), "abcd");
+        assert!(history.can_redo().get());
+
+        // A new change drops what could be redone.
+        text.set("x");
+        assert!(!history.can_redo().get());
+        history.undo();
+        assert_eq!(text.get(), "abcd");
+    }
+
+    #[test]
+    fn history_records_without_re_rendering_its_scope() {
+        let _guard = TEST_MUTEX.lock();
+        reset_runtime_state();
+
+        let renders = Arc::new(AtomicUsize::new(0));
+        let counter = renders.clone();
+        let ((count, history), _) = in_root_scope(move || {
+            counter.fetch_add(1, Ordering::SeqCst);
+            let count = create_signal(0);
+            (count, create_history(count, 10))
+        });
+
+        count.set(1);
+        count.set(2);
+        assert_eq!(renders.load(Ordering::SeqCst), 1);
+        history.undo();
+        assert_eq!(count.get(), 1);
+        assert_eq!(renders.load(Ordering::SeqCst), 1);
+    }
+
+    #[test]
+    fn history_coalesces_rapid_changes() {
+        let _guard = TEST_MUTEX.lock();
+        reset_runtime_state();
+        let clock = VirtualClock::new();
+        set_timer(clock.clone());
+
+        let ((count, history), _) = in_root_scope(move || {
+            let count = create_signal(0);
+            let history = create_history_with_coalescing(count, 10, 100);
+            let _ = count.get();
+            (count, history)
+        });
+
+        count.set(1);
+        clock.advance(50);
+        count.set(2);
+        // The window is measured from the start of the entry, so a steady
+        // stream of changes is still split up.
+        clock.advance(50);
+        count.set(3);
+        clock.advance(150);
+        count.set(4);
+
+        history.undo();
+        assert_eq!(count.get(), 3);
+        history.undo();
+        assert_eq!(count.get(), 2);
+        history.undo();
+        assert_eq!(count.get(), 0);
+
+        *TIMER.lock() = None;
+    }
+}
@@ -0,0 +1,317 @@
+//! Keyed lists: [`map_keyed`] and the [`ListPatcher`] that applies their
+//! changes in place.
+
+use super::{ScopeHandle, Signal, SignalValue, run_scope, runtime};
+use crate::nodes::Node;
+use alloc::{
+    collections::BTreeMap,
+    string::{String, ToString},
+    sync::Arc,
+    vec::Vec,
+};
+use spin::Mutex;
+
+/// Prefix of the comment [`map_keyed`] puts after the items of a list, which
+/// a [`ListPatcher`] uses to find where the list was rendered.
+pub const LIST_MARKER_PREFIX: &str = "momenta-list:";
+
+/// Updates rendered output in place when a [`map_keyed`] list changes.
+///
+/// `momenta_dom` installs one backed by its element cache when it renders.
+/// Without one, lists still keep their items, but the output only changes
+/// when the scope around the list re-renders.
+pub trait ListPatcher: Send + Sync {
+    /// An item re-rendered on its own; `node` replaces the output rendered
+    /// with the same key.
+    fn patch_item(&self, node: &Node);
+    /// The list ending at the comment `marker` changed. `items` is its new
+    /// output in order, in which kept items carry the key they were first
+    /// rendered with, and `removed` holds the keys of the items that are gone.
+    fn patch_list(&self, marker: &str, items: &[&Node], removed: &[String]);
+    /// The list ending at the comment `marker` was disposed; anything kept
+    /// to find it again can be dropped.
+    fn dispose_list(&self, _marker: &str) {}
+}
+
+static LIST_PATCHER: Mutex<Option<Arc<dyn ListPatcher>>> = Mutex::new(None);
+
+/// Install the patcher that applies [`map_keyed`] changes to rendered output.
+///
+/// The patcher is process-global: it is shared by every [`Runtime`](super::Runtime) and
+/// replaces any patcher installed before.
+pub fn set_list_patcher(patcher: impl ListPatcher + 'static) {
+    *LIST_PATCHER.lock() = Some(Arc::new(patcher));
+}
+
+/// Returns true once [`set_list_patcher`] has been called.
+pub fn has_list_patcher() -> bool {
+    LIST_PATCHER.lock().is_some()
+}
+
+pub(super) fn installed_list_patcher() -> Option<Arc<dyn ListPatcher>> {
+    LIST_PATCHER.lock().clone()
+}
+
+pub(super) fn list_marker(scope_id: usize) -> String {
+    alloc::format!("{LIST_MARKER_PREFIX}{scope_id}")
+}
+
+/// Render `list` with one scope per key, keeping each item's scope and output
+/// for as long as its key stays in the list.
+///
+/// When `list` changes, only items with new keys are rendered and only items
+/// whose keys are gone are disposed; the others keep their output and are
+/// just moved, which an installed [`ListPatcher`] applies in place. An item
+/// whose value changed under the same key is rendered again. Signals read by
+/// `render_fn` re-render that item alone. Keys are expected to be unique.
+/// `key_fn` runs while the list is borrowed, like the closure given to
+/// [`Signal::with`].
+///
+/// The returned fragment ends with a marker comment (see
+/// [`LIST_MARKER_PREFIX`]). Give every item a single root element so it can be
+/// moved and removed on its own.
+///
+/// # Example
+/// ```ignore
+/// use momenta_core::signals::map_keyed;
+///
+/// rsx! {
+///     <ul>
+///         {map_keyed(todos, |todo| todo.id, |todo| rsx! { <li>{&todo.title}</li> })}
+///     </ul>
+/// }
+/// ```
+pub fn map_keyed<T, K, KF, RF>(list: Signal<Vec<T>>, key_fn: KF, render_fn: RF) -> Node
+where
+    T: SignalValue + PartialEq + Clone + 'static,
+    K: Ord + Send + 'static,
+    KF: Fn(&T) -> K + Send + 'static,
+    RF: Fn(&T) -> Node + Send + Sync + 'static,
+{
+    let render_fn = Arc::new(render_fn);
+    let mut entries: Vec<(K, KeyedItem<T>)> = Vec::new();
+    run_scope(
+        move || {
+            let (scope_id, first_render) = {
+                let mut rt = runtime().lock();
+                let scope_id = rt.owner_scope();
+                let scope = rt.ensure_scope(scope_id);
+                let first_render = !core::mem::replace(&mut scope.keeps_children, true);
+                (scope_id, first_render)
+            };
+
+            let mut previous: BTreeMap<K, (usize, KeyedItem<T>)> = entries
+                .drain(..)
+                .enumerate()
+                .map(|(index, (key, item))| (key, (index, item)))
+                .collect();
+            // Stale items are disposed only once the new ones are rendered, so
+            // no new item reuses the key of one that is being removed.
+            let mut stale = Vec::new();
+            let mut changed = false;
+            // Only values that have to be rendered are copied out of the list.
+            let slots: Vec<(K, Result<KeyedItem<T>, T>)> = list
+                .with(|values| {
+                    values
+                        .iter()
+                        .enumerate()
+                        .map(|(position, value)| {
+                            let key = key_fn(value);
+                            let slot = match previous.remove(&key) {
+                                Some((index, item)) if item.value == *value => {
+                                    changed |= index != position;
+                                    Ok(item)
+                                }
+                                replaced => {
+                                    stale.extend(replaced.map(|(_, item)| item));
+                                    changed = true;
+                                    Err(value.clone())
+                                }
+                            };
+                            (key, slot)
+                        })
+                        .collect()
+                })
+                .unwrap_or_default();
+            for (key, slot) in slots {
+                let item = slot.unwrap_or_else(|value| KeyedItem::render(value, render_fn.clone()));
+                entries.push((key, item));
+            }
+            stale.extend(previous.into_values().map(|(_, item)| item));
+            changed |= !stale.is_empty();
+            let removed: Vec<_> = stale.into_iter().map(KeyedItem::dispose).collect();
+
+            let marker = list_marker(scope_id);
+            if first_render {
+                let mut nodes: Vec<Node> = entries.iter().map(|(_, item)| item.node()).collect();
+                nodes.push(Node::Comment(marker));
+                return Node::Fragment(nodes);
+            }
+            // Later renders only reach the patcher; the fragment above is what
+            // stays in the tree.
+            if changed && let Some(patcher) = installed_list_patcher() {
+                let outputs: Vec<_> = entries.iter().map(|(_, item)| item.output.lock()).collect();
+                let items: Vec<&Node> = outputs.iter().map(|output| &output.node).collect();
+                patcher.patch_list(&marker, &items, &removed);
+            }
+            Node::Empty
+        },
+        |_| {},
+    )
+}
+
+/// One rendered item of a [`map_keyed`] list.
+struct KeyedItem<T> {
+    value: T,
+    output: Arc<Mutex<ItemOutput>>,
+}
+
+struct ItemOutput {
+    scope: Option<ScopeHandle>,
+    node: Node,
+}
+
+impl<T: Clone + Send + 'static> KeyedItem<T> {
+    fn render(value: T, render_fn: Arc<impl Fn(&T) -> Node + Send + Sync + 'static>) -> Self {
+        let output = Arc::new(Mutex::new(ItemOutput {
+            scope: None,
+            node: Node::Empty,
+        }));
+        let captured = output.clone();
+        let item = value.clone();
+        run_scope(
+            move || render_fn(&item),
+            move |node| {
+                let rerender = {
+                    let mut output = captured.lock();
+                    output.node = node.clone();
+                    output
+                        .scope
+                        .replace(ScopeHandle::current().unwrap())
+                        .is_some()
+                };
+                if rerender && let Some(patcher) = installed_list_patcher() {
+                    patcher.patch_item(node);
+                }
+            },
+        );
+        Self { value, output }
+    }
+
+    fn node(&self) -> Node {
+        self.output.lock().node.clone()
+    }
+
+    /// Dispose the item's scope, returning the key its output was rendered with.
+    fn dispose(self) -> String {
+        let scope = self.output.lock().scope.take();
+        match scope {
+            Some(scope) => {
+                scope.dispose();
+                scope.id.to_string()
+            }
+            None => String::new(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::signals::{TEST_MUTEX, create_signal, reset_runtime_state};
+    use alloc::vec;
+
+    #[test]
+    fn keyed_lists_only_render_and_dispose_changed_keys() {
+        let _guard = TEST_MUTEX.lock();
+        reset_runtime_state();
+
+        #[derive(Default)]
+        struct Patches {
+            lists: Vec<(Vec<String>, Vec<String>)>,
+            items: Vec<String>,
+            disposed: Vec<String>,
+        }
+        #[derive(Clone, Default)]
+        struct Recorder(Arc<spin::Mutex<Patches>>);
+        impl ListPatcher for Recorder {
+            fn patch_item(&self, node: &Node) {
+                let key = node.as_element().unwrap().key().to_string();
+                self.0.lock().items.push(key);
+            }
+            fn patch_list(&self, marker: &str, items: &[&Node], removed: &[String]) {
+                assert!(marker.starts_with(LIST_MARKER_PREFIX));
+                let keys = items
+                    .iter()
+                    .map(|node| node.as_element().unwrap().key().to_string())
+                    .collect();
+                self.0.lock().lists.push((keys, removed.to_vec()));
+            }
+            fn dispose_list(&self, marker: &str) {
+                self.0.lock().disposed.push(marker.to_string());
+            }
+        }
+
+        let renders = Arc::new(spin::Mutex::new(Vec::new()));
+        let slot = Arc::new(spin::Mutex::new(None));
+        let (log, captured) = (renders.clone(), slot.clone());
+        let html = run_scope(
+            move || {
+                let rows = create_signal(vec![(1, "a"), (2, "b"), (3, "c")]);
+                let suffix = create_signal("");
+                *captured.lock() = Some((rows, suffix, ScopeHandle::current().unwrap()));
+                let log = log.clone();
+                map_keyed(
+                    rows,
+                    |row| row.0,
+                    move |&(id, label)| {
+                        log.lock().push(label);
+                        let suffix = if id == 1 { suffix.get() } else { "" };
+                        let text = Node::Text(alloc::format!("{label}{suffix}"));
+                        crate::nodes::Element::parse_tag_with_attributes(
+                            "",
+                            "li",
+                            vec![],
+                            vec![],
+                            "",
+                            vec![text],
+                        )
+                    },
+                )
+            },
+            |_| {},
+        )
+        .to_string();
+        assert!(html.starts_with("<li>a</li><li>b</li><li>c</li><!--momenta-list:"));
+
+        let recorder = Recorder::default();
+        set_list_patcher(recorder.clone());
+        let (rows, suffix, scope) = slot.lock().unwrap();
+        let keys = |patch: &(Vec<String>, Vec<String>)| (patch.0.clone(), patch.1.clone());
+
+        rows.set(vec![(3, "c"), (1, "a"), (4, "d")]);
+        assert_eq!(*renders.lock(), ["a", "b", "c", "d"]);
+        let (first, removed) = keys(&recorder.0.lock().lists[0]);
+        assert_eq!(first.len(), 3);
+        assert_eq!(removed.len(), 1);
+
+        rows.set(vec![(3, "C"), (1, "a"), (4, "d")]);
+        assert_eq!(*renders.lock(), ["a", "b", "c", "d", "C"]);
+        let (second, removed) = keys(&recorder.0.lock().lists[1]);
+        assert_eq!(second[1..], first[1..]);
+        assert_eq!(removed, [first[0].clone()]);
+
+        suffix.set("!");
+        assert_eq!(*renders.lock(), ["a", "b", "c", "d", "C", "a"]);
+        assert_eq!(recorder.0.lock().items, [second[1].clone()]);
+        assert_eq!(recorder.0.lock().lists.len(), 2);
+
+        // The patcher is told when the list is gone, so it can forget its marker.
+        let marker = html.split("<!--").nth(1).unwrap().trim_end_matches("-->");
+        assert!(recorder.0.lock().disposed.is_empty());
+        scope.dispose();
+        assert_eq!(recorder.0.lock().disposed, [marker]);
+
+        *LIST_PATCHER.lock() = None;
+    }
+}
@@ -0,0 +1,184 @@
+//! Selectors: subscriptions to whether a signal holds one particular value.
+
+use super::{Signal, SignalValue, runtime};
+use core::{
+    any::Any,
+    hash::{Hash, Hasher},
+};
+
+/// Checks which value a signal holds, created with [`create_selector`].
+#[derive(Debug)]
+pub struct Selector<T> {
+    source: Signal<T>,
+}
+
+impl<T> Copy for Selector<T> {}
+
+impl<T> Clone for Selector<T> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<T: SignalValue + PartialEq + Hash + 'static> Selector<T> {
+    /// True if the source holds `key`. The current scope is only re-rendered
+    /// when the source changes to or away from `key`.
+    pub fn is(&self, key: &T) -> bool {
+        runtime()
+            .lock()
+            .track_key(self.source.id, &[selection_key(key)]);
+        self.source
+            .with_untracked(|value| value == key)
+            .unwrap_or(false)
+    }
+}
+
+/// Subscribe scopes to whether `source` holds one particular value, instead of
+/// to every change of it.
+///
+/// With one row per item and a selected id, a row that asks
+/// `selected.is(&id)` is re-rendered only when it gains or loses the
+/// selection, so a change re-renders two rows instead of the whole list.
+///
+/// # Example
+/// ```ignore
+/// use momenta::prelude::*;
+///
+/// let selected = create_signal(0);
+/// let is_selected = create_selector(selected);
+///
+/// rsx! {
+///     <ul>
+///         {rows.iter().map(|row| rsx! {
+///             <li class={if is_selected.is(&row.id) { "selected" } else { "" }}>
+///                 {&row.label}
+///             </li>
+///         })}
+///     </ul>
+/// }
+/// ```
+pub fn create_selector<T>(source: Signal<T>) -> Selector<T>
+where
+    T: SignalValue + PartialEq + Hash + 'static,
+{
+    if let Some(current) = source.with_untracked(selection_key) {
+        runtime()
+            .lock()
+            .selectors
+            .entry(source.id)
+            .or_insert((stored_selection_key::<T>, current));
+    }
+    Selector { source }
+}
+
+fn stored_selection_key<T: Hash + 'static>(value: &dyn Any) -> Option<u64> {
+    value.downcast_ref::<T>().map(selection_key)
+}
+
+/// Hash a selected value to a key. Values with the same key re-render
+/// together, so a collision only costs an extra render.
+fn selection_key<T: Hash>(value: &T) -> u64 {
+    let mut hasher = KeyHasher(0xcbf2_9ce4_8422_2325);
+    value.hash(&mut hasher);
+    hasher.finish()
+}
+
+/// FNV-1a, which needs neither `std` nor random state.
+struct KeyHasher(u64);
+
+impl Hasher for KeyHasher {
+    fn finish(&self) -> u64 {
+        self.0
+    }
+
+    fn write(&mut self, bytes: &[u8]) {
+        for &byte in bytes {
+            self.0 = (self.0 ^ u64::from(byte)).wrapping_mul(0x0100_0000_01b3);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::nodes::Node;
+    use crate::signals::{
+        TEST_MUTEX, create_signal, in_root_scope, reset_runtime_state, run_scope,
+    };
+    use alloc::{string::ToString, sync::Arc, vec::Vec};
+    use core::sync::atomic::{AtomicUsize, Ordering};
+
+    #[test]
+    fn selector_rerenders_only_the_old_and_new_selection() {
+        let _guard = TEST_MUTEX.lock();
+        reset_runtime_state();
+
+        let ((selected, selector), _) = in_root_scope(move || {
+            let selected = create_signal(0);
+            (selected, create_selector(selected))
+        });
+
+        let rows: Vec<_> = (0..4)
+            .map(|row| {
+                let renders = Arc::new(AtomicUsize::new(0));
+                let renders_clone = renders.clone();
+                run_scope(
+                    move || {
+                        renders_clone.fetch_add(1, Ordering::SeqCst);
+                        Node::Text(selector.is(&row).to_string())
+                    },
+                    |_| {},
+                );
+                renders
+            })
+            .collect();
+        let counts = || {
+            rows.iter()
+                .map(|r| r.load(Ordering::SeqCst))
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(counts(), [1, 1, 1, 1]);
+
+        selected.set(2);
+        assert_eq!(counts(), [2, 1, 2, 1]);
+        assert!(selector.is(&2) && !selector.is(&0));
+
+        selected.set(2);
+        selected.set(3);
+        assert_eq!(counts(), [2, 1, 3, 2]);
+    }
+
+    #[test]
+    fn selector_rerenders_values_whose_keys_collide() {
+        let _guard = TEST_MUTEX.lock();
+        reset_runtime_state();
+
+        /// Every value hashes the same.
+        #[derive(Clone, Copy, PartialEq)]
+        struct Id(u32);
+        impl Hash for Id {
+            fn hash<H: Hasher>(&self, _: &mut H) {}
+        }
+
+        let ((selected, selector), _) = in_root_scope(move || {
+            let selected = create_signal(Id(0));
+            (selected, create_selector(selected))
+        });
+
+        let shown = Arc::new(spin::Mutex::new(Vec::new()));
+        for row in 0..2 {
+            let shown = shown.clone();
+            run_scope(
+                move || {
+                    shown.lock().push((row, selector.is(&Id(row))));
+                    Node::Empty
+                },
+                |_| {},
+            );
+        }
+        assert_eq!(*shown.lock(), [(0, true), (1, false)]);
+
+        selected.set(Id(1));
+        assert_eq!(shown.lock()[2..], [(0, false), (1, true)]);
+    }
+}
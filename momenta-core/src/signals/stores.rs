@@ -0,0 +1,586 @@
+//! Stores: structs whose fields are read and written through [`Lens`]es.
+
+use super::{
+    DependencyKey, InUse, Revert, RuntimeState, SignalError, StoredValue, TakenValue,
+    process_pending_renders, restore, runtime,
+};
+use alloc::{boxed::Box, sync::Arc, vec::Vec};
+use core::{any::Any, marker::PhantomData, panic::Location};
+
+type Projection = Arc<dyn for<'a> Fn(&'a dyn Any) -> Option<&'a dyn Any> + Send + Sync>;
+type ProjectionMut = Arc<dyn for<'a> Fn(&'a mut dyn Any) -> Option<&'a mut dyn Any> + Send + Sync>;
+
+/// Key segment standing for the length of a `Vec` in a store path.
+const LEN_KEY: u64 = u64::MAX;
+
+fn projection<F>(f: F) -> Projection
+where
+    F: for<'a> Fn(&'a dyn Any) -> Option<&'a dyn Any> + Send + Sync + 'static,
+{
+    Arc::new(f)
+}
+
+fn projection_mut<F>(f: F) -> ProjectionMut
+where
+    F: for<'a> Fn(&'a mut dyn Any) -> Option<&'a mut dyn Any> + Send + Sync + 'static,
+{
+    Arc::new(f)
+}
+
+/// True when a change at `path` affects a reader of `key`: the reader looked at
+/// the changed part, one of its ancestors, or an element at or after `from`
+/// below it.
+pub(super) fn path_affects(path: &[u64], from: u64, key: &[u64]) -> bool {
+    key.iter().zip(path).all(|(a, b)| a == b)
+        && key.get(path.len()).is_none_or(|&segment| segment >= from)
+}
+
+/// A struct whose fields can be read and written one at a time through
+/// [`Lens`]es. Implement it with `#[derive(Store)]`, which generates a
+/// `<Name>Store` type with an accessor for every field.
+pub trait Store: Send + Sized + 'static {
+    /// The generated field accessors.
+    type Fields;
+
+    fn fields(lens: Lens<Self>) -> Self::Fields;
+}
+
+/// A reactive view of one part of a store.
+///
+/// Reading through a lens subscribes the current scope to that part only.
+/// Writing through it re-renders the scopes that read the same part, one of
+/// its ancestors or something inside it, and leaves every other reader of the
+/// store alone. Computed signals depend on the store as a whole.
+pub struct Lens<T> {
+    signal: (usize, usize),
+    generation: u32,
+    /// Where the store was created; only known in debug builds.
+    created_at: Option<&'static Location<'static>>,
+    path: DependencyKey,
+    project: Projection,
+    project_mut: ProjectionMut,
+    _marker: PhantomData<fn() -> T>,
+}
+
+impl<T> Clone for Lens<T> {
+    fn clone(&self) -> Self {
+        Self {
+            signal: self.signal,
+            generation: self.generation,
+            created_at: self.created_at,
+            path: self.path.clone(),
+            project: self.project.clone(),
+            project_mut: self.project_mut.clone(),
+            _marker: PhantomData,
+        }
+    }
+}
+
+impl<T: 'static> Lens<T> {
+    #[track_caller]
+    fn root(signal: (usize, usize), generation: u32) -> Self {
+        Self {
+            signal,
+            generation,
+            created_at: cfg!(debug_assertions).then(Location::caller),
+            path: Vec::new(),
+            project: projection(|v| Some(v)),
+            project_mut: projection_mut(|v| Some(v)),
+            _marker: PhantomData,
+        }
+    }
+
+    fn child<U: 'static>(
+        &self,
+        segment: u64,
+        get: impl for<'a> Fn(&'a T) -> Option<&'a U> + Send + Sync + 'static,
+        get_mut: impl for<'a> Fn(&'a mut T) -> Option<&'a mut U> + Send + Sync + 'static,
+    ) -> Lens<U> {
+        let parent = self.project.clone();
+        let parent_mut = self.project_mut.clone();
+        let mut path = self.path.clone();
+        path.push(segment);
+        Lens {
+            signal: self.signal,
+            generation: self.generation,
+            created_at: self.created_at,
+            path,
+            project: projection(move |root| {
+                parent(root)?
+                    .downcast_ref::<T>()
+                    .and_then(&get)
+                    .map(|v| v as &dyn Any)
+            }),
+            project_mut: projection_mut(move |root| {
+                parent_mut(root)?
+                    .downcast_mut::<T>()
+                    .and_then(&get_mut)
+                    .map(|v| v as &mut dyn Any)
+            }),
+            _marker: PhantomData,
+        }
+    }
+
+    /// Narrow the lens to one field. Used by `#[derive(Store)]`.
+    #[doc(hidden)]
+    pub fn field<U: 'static>(
+        &self,
+        index: u64,
+        get: fn(&T) -> &U,
+        get_mut: fn(&mut T) -> &mut U,
+    ) -> Lens<U> {
+        self.child(index, move |v| Some(get(v)), move |v| Some(get_mut(v)))
+    }
+
+    /// The store's value, unless its scope was disposed since the lens was
+    /// created.
+    fn stored<'a>(&self, rt: &'a mut RuntimeState) -> Result<&'a mut StoredValue, SignalError> {
+        let disposed = SignalError::Disposed {
+            signal: self.signal,
+            created_at: self.created_at,
+        };
+        if rt.scope_generation(self.signal.0) != self.generation {
+            return Err(disposed);
+        }
+        rt.get_signal_mut(self.signal).ok_or(disposed)
+    }
+
+    fn read<R>(&self, key: &[u64], f: impl FnOnce(&T) -> R) -> Result<R, SignalError> {
+        let mut rt = runtime().lock();
+        self.stored(&mut rt)?;
+        rt.track_key(self.signal, key);
+        let root = &*self.stored(&mut rt)?.value;
+        if root.is::<InUse>() {
+            return Err(SignalError::InUse {
+                signal: self.signal,
+                created_at: self.created_at,
+            });
+        }
+        let part = (self.project)(root).ok_or(SignalError::MissingPart {
+            signal: self.signal,
+            created_at: self.created_at,
+        })?;
+        part.downcast_ref::<T>()
+            .map(f)
+            .ok_or(SignalError::TypeMismatch {
+                signal: self.signal,
+                expected: core::any::type_name::<T>(),
+                created_at: self.created_at,
+            })
+    }
+
+    /// Apply `f` to the value, telling it whether a transaction is open. If it
+    /// reports a change, together with the first item index it touched and, in
+    /// a transaction, how to revert it, re-render the readers it affected.
+    ///
+    /// Like [`Signal::update`], the store is taken out of the runtime while
+    /// `f` runs, so `f` and the old values it drops never run under the lock,
+    /// and put back if `f` panics.
+    fn write<R>(
+        &self,
+        f: impl FnOnce(&mut T, bool) -> Option<(R, u64, Option<Revert<T>>)>,
+    ) -> Option<R> {
+        let (mut taken, journal) = {
+            let mut rt = runtime().lock();
+            let journal = !rt.transactions.is_empty();
+            let stored = self.stored(&mut rt).ok()?;
+            if stored.value.is::<InUse>() {
+                return None;
+            }
+            (
+                TakenValue::take(self.signal, self.generation, stored),
+                journal,
+            )
+        };
+
+        let outcome = taken
+            .value
+            .as_mut()
+            .and_then(|root| (self.project_mut)(&mut **root))
+            .and_then(|part| part.downcast_mut::<T>())
+            .and_then(|current| f(current, journal));
+        let (root, eq) = taken.into_parts();
+
+        let (result, should_process) = {
+            let mut rt = runtime().lock();
+            // If `f` disposed the store, it is dropped once the lock is
+            // released.
+            let Ok(stored) = self.stored(&mut rt) else {
+                return outcome.map(|(result, ..)| result);
+            };
+            stored.value = root;
+            stored.eq = eq;
+            let (result, from, revert) = outcome?;
+
+            match revert {
+                Some(Revert::Restore(revert)) => {
+                    let lens = self.clone();
+                    rt.record_undo(move |rt| {
+                        let current = lens
+                            .stored(rt)
+                            .ok()
+                            .and_then(|stored| (lens.project_mut)(&mut *stored.value))
+                            .and_then(|any| any.downcast_mut::<T>());
+                        if let Some(current) = current {
+                            revert(current);
+                            rt.part_changed(lens.signal, &lens.path, from);
+                        }
+                    });
+                }
+                Some(Revert::Irreversible) => rt.record_irreversible(self.signal),
+                None => {}
+            }
+            (result, rt.part_changed(self.signal, &self.path, from))
+        };
+
+        if should_process {
+            process_pending_renders();
+        }
+        Some(result)
+    }
+
+    /// Returns `None` if the part no longer exists, e.g. an index past the end
+    /// of a list.
+    pub fn with<R>(&self, f: impl FnOnce(&T) -> R) -> Option<R> {
+        self.read(&self.path, f).ok()
+    }
+
+    /// Like [`Lens::with`], but reports why the value could not be read.
+    pub fn try_with<R>(&self, f: impl FnOnce(&T) -> R) -> Result<R, SignalError> {
+        self.read(&self.path, f)
+    }
+
+    /// # Panics
+    /// If the value can no longer be read, see [`Lens::try_get`].
+    #[track_caller]
+    pub fn get(&self) -> T
+    where
+        T: Clone,
+    {
+        self.try_get().unwrap_or_else(|err| panic!("{err}"))
+    }
+
+    /// Like [`Lens::get`], but reports why the value could not be read
+    /// instead of panicking, e.g. [`SignalError::MissingPart`] for an item
+    /// past the end of a list.
+    pub fn try_get(&self) -> Result<T, SignalError>
+    where
+        T: Clone,
+    {
+        self.read(&self.path, T::clone)
+    }
+
+    pub fn set(&self, value: T)
+    where
+        T: PartialEq + Send,
+    {
+        self.write(|current, journal| {
+            if *current == value {
+                return None;
+            }
+            let previous = core::mem::replace(current, value);
+            Some(((), 0, journal.then(|| restore(previous))))
+        });
+    }
+}
+
+impl<T: Store> Lens<T> {
+    /// The field accessors of a nested store, e.g. for an item of a list.
+    pub fn fields(&self) -> T::Fields {
+        T::fields(self.clone())
+    }
+}
+
+impl<T: 'static> Lens<Vec<T>> {
+    /// A lens onto one item. Readers of an item are only re-rendered when that
+    /// item changes or moves.
+    pub fn at(&self, index: usize) -> Lens<T> {
+        self.child(
+            index as u64,
+            move |items: &Vec<T>| items.get(index),
+            move |items: &mut Vec<T>| items.get_mut(index),
+        )
+    }
+
+    /// Subscribes to the length only, not to the items.
+    pub fn len(&self) -> usize {
+        let mut key = self.path.clone();
+        key.push(LEN_KEY);
+        self.read(&key, Vec::len).unwrap_or(0)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn push(&self, item: T) {
+        self.write(|items, journal| {
+            items.push(item);
+            let revert = journal.then(|| -> Revert<Vec<T>> {
+                Revert::Restore(Box::new(|items| {
+                    items.pop();
+                }))
+            });
+            Some(((), items.len() as u64 - 1, revert))
+        });
+    }
+
+    /// In a transaction the item is cloned so that it can be put back.
+    pub fn pop(&self) -> Option<T>
+    where
+        T: Clone + Send,
+    {
+        self.write(|items, journal| {
+            let item = items.pop()?;
+            let revert = journal.then(|| -> Revert<Vec<T>> {
+                let item = item.clone();
+                Revert::Restore(Box::new(move |items| items.push(item)))
+            });
+            Some((item, items.len() as u64, revert))
+        })
+    }
+
+    /// Items after `index` move, so their readers re-render as well.
+    pub fn insert(&self, index: usize, item: T) {
+        self.write(|items, journal| {
+            items.insert(index, item);
+            let revert = journal.then(|| -> Revert<Vec<T>> {
+                Revert::Restore(Box::new(move |items| {
+                    items.remove(index);
+                }))
+            });
+            Some(((), index as u64, revert))
+        });
+    }
+
+    /// Returns `None` if `index` is out of bounds. In a transaction the item
+    /// is cloned so that it can be put back.
+    pub fn remove(&self, index: usize) -> Option<T>
+    where
+        T: Clone + Send,
+    {
+        self.write(|items, journal| {
+            if index >= items.len() {
+                return None;
+            }
+            let item = items.remove(index);
+            let revert = journal.then(|| -> Revert<Vec<T>> {
+                let item = item.clone();
+                Revert::Restore(Box::new(move |items| items.insert(index, item)))
+            });
+            Some((item, index as u64, revert))
+        })
+    }
+
+    pub fn clear(&self)
+    where
+        T: Send,
+    {
+        self.write(|items, journal| {
+            if items.is_empty() {
+                return None;
+            }
+            let previous = core::mem::take(items);
+            Some(((), 0, journal.then(|| restore(previous))))
+        });
+    }
+}
+
+/// Create a store in the current scope and return its field accessors.
+///
+/// # Example
+/// ```ignore
+/// use momenta::prelude::*;
+///
+/// #[derive(Store)]
+/// struct User {
+///     name: String,
+///     age: u32,
+/// }
+///
+/// #[derive(Store)]
+/// struct AppState {
+///     #[store(nested)]
+///     user: User,
+///     todos: Vec<String>,
+/// }
+///
+/// let state = create_store(AppState { /* ... */ });
+///
+/// // Only scopes that read the user's name re-render.
+/// state.user().name().set("Ada".to_string());
+/// // Only scopes that read the first todo (or the whole list) re-render.
+/// state.todos().at(0).set("Write docs".to_string());
+/// ```
+#[track_caller]
+pub fn create_store<T: Store>(value: T) -> T::Fields {
+    let signal = {
+        let mut rt = runtime().lock();
+        let scope_id = rt.owner_scope();
+        let scope = rt.ensure_scope(scope_id);
+        scope.signal_count += 1;
+        // On re-render the existing store is kept and `value` is dropped.
+        if scope.signals.len() < scope.signal_count {
+            scope.signals.push(StoredValue::new(Box::new(value)));
+        }
+        (
+            (scope_id, scope.signal_count),
+            rt.scope_generation(scope_id),
+        )
+    };
+    T::fields(Lens::root(signal.0, signal.1))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::nodes::Node;
+    use crate::signals::{TEST_MUTEX, in_root_scope, reset_runtime_state, run_scope};
+    use alloc::{
+        string::{String, ToString},
+        vec,
+    };
+    use core::sync::atomic::{AtomicUsize, Ordering};
+
+    struct Board {
+        title: String,
+        items: Vec<u32>,
+    }
+
+    #[derive(Clone)]
+    struct BoardFields(Lens<Board>);
+
+    impl Store for Board {
+        type Fields = BoardFields;
+
+        fn fields(lens: Lens<Self>) -> Self::Fields {
+            BoardFields(lens)
+        }
+    }
+
+    impl BoardFields {
+        fn title(&self) -> Lens<String> {
+            self.0.field(0, |b| &b.title, |b| &mut b.title)
+        }
+
+        fn items(&self) -> Lens<Vec<u32>> {
+            self.0.field(1, |b| &b.items, |b| &mut b.items)
+        }
+    }
+
+    #[test]
+    fn store_updates_only_rerender_readers_of_the_changed_part() {
+        let _guard = TEST_MUTEX.lock();
+        reset_runtime_state();
+
+        let (board, _) = in_root_scope(|| {
+            create_store(Board {
+                title: "todo".to_string(),
+                items: vec![1, 2],
+            })
+        });
+
+        let reader = |read: Box<dyn Fn(&BoardFields) + Send>| {
+            let renders = Arc::new(AtomicUsize::new(0));
+            let (renders_clone, board) = (renders.clone(), board.clone());
+            run_scope(
+                move || {
+                    renders_clone.fetch_add(1, Ordering::SeqCst);
+                    read(&board);
+                    Node::Empty
+                },
+                |_| {},
+            );
+            renders
+        };
+        let title = reader(Box::new(|b| {
+            let _ = b.title().get();
+        }));
+        let first = reader(Box::new(|b| {
+            let _ = b.items().at(0).with(|_| ());
+        }));
+        let second = reader(Box::new(|b| {
+            let _ = b.items().at(1).with(|_| ());
+        }));
+        let len = reader(Box::new(|b| {
+            let _ = b.items().len();
+        }));
+        let counts =
+            || [&title, &first, &second, &len].map(|renders| renders.load(Ordering::SeqCst));
+        assert_eq!(counts(), [1, 1, 1, 1]);
+
+        board.title().set("done".to_string());
+        assert_eq!(counts(), [2, 1, 1, 1]);
+
+        board.items().at(1).set(3);
+        assert_eq!(counts(), [2, 1, 2, 1]);
+
+        // Setting the same value is not a change.
+        board.items().at(1).set(3);
+        assert_eq!(counts(), [2, 1, 2, 1]);
+
+        board.items().push(4);
+        assert_eq!(counts(), [2, 1, 2, 2]);
+
+        // Removing the first item moves every item after it.
+        assert_eq!(board.items().remove(0), Some(1));
+        assert_eq!(counts(), [2, 2, 3, 3]);
+        assert_eq!(board.items().get(), vec![3, 4]);
+    }
+
+    #[test]
+    fn lens_reads_report_missing_parts_and_writes_run_without_the_lock() {
+        let _guard = TEST_MUTEX.lock();
+        reset_runtime_state();
+
+        type Log = Arc<spin::Mutex<Vec<bool>>>;
+        /// Records whether the runtime was free when it was compared or dropped.
+        struct Book(Log);
+        impl PartialEq for Book {
+            fn eq(&self, _: &Self) -> bool {
+                self.0.lock().push(runtime().state.try_lock().is_some());
+                false
+            }
+        }
+        impl Drop for Book {
+            fn drop(&mut self) {
+                self.0.lock().push(runtime().state.try_lock().is_some());
+            }
+        }
+        struct Shelf(Vec<Book>);
+        impl Store for Shelf {
+            type Fields = Lens<Shelf>;
+
+            fn fields(lens: Lens<Self>) -> Lens<Self> {
+                lens
+            }
+        }
+
+        let log: Log = Arc::new(spin::Mutex::new(Vec::new()));
+        let book_log = log.clone();
+        let (shelf, scope) =
+            in_root_scope(move || create_store(Shelf(vec![Book(book_log.clone())])));
+        let books = shelf.field(0, |s| &s.0, |s| &mut s.0);
+
+        assert_eq!(books.at(0).try_with(|_| ()), Ok(()));
+        assert_eq!(
+            books.at(3).try_with(|_| ()),
+            Err(SignalError::MissingPart {
+                signal: books.signal,
+                created_at: books.created_at,
+            })
+        );
+
+        books.at(0).set(Book(log.clone()));
+        assert_eq!(*log.lock(), [true, true]);
+
+        scope.dispose();
+        assert_eq!(
+            books.try_with(|_| ()),
+            Err(SignalError::Disposed {
+                signal: books.signal,
+                created_at: books.created_at,
+            })
+        );
+    }
+}
@@ -0,0 +1,233 @@
+//! Conversions between signals and [`Stream`]s.
+
+use super::{Signal, SignalValue, create_signal, runtime, spawn};
+use alloc::{boxed::Box, sync::Arc, vec::Vec};
+use core::{
+    any::Any,
+    pin::Pin,
+    task::{Context, Poll, Waker},
+};
+use futures_core::Stream;
+use spin::Mutex;
+
+impl<T: SignalValue + Clone + 'static> Signal<T> {
+    /// A [`Stream`] of the values this signal takes from now on.
+    ///
+    /// The current value is not yielded. Changes made while the stream is not
+    /// polled are coalesced: only the latest one is yielded, so a slow reader
+    /// never falls behind. Memos yield when they are recomputed. The stream
+    /// ends once the signal's scope is disposed, and ends right away if it
+    /// already has been.
+    ///
+    /// # Example
+    /// ```ignore
+    /// use futures::StreamExt;
+    ///
+    /// let mut changes = count.to_stream();
+    /// while let Some(count) = changes.next().await {
+    ///     println!("count is now {count}");
+    /// }
+    /// ```
+    pub fn to_stream(&self) -> SignalStream<T> {
+        let state = Arc::new(Mutex::new(StreamState {
+            latest: None,
+            waker: None,
+            closed: false,
+        }));
+        let sender = StreamSender(state.clone());
+        let mut rt = runtime().lock();
+        if rt.stored(self).is_ok() {
+            rt.listeners
+                .entry(self.id)
+                .or_default()
+                .push(Box::new(move |value, wakers| sender.send(value, wakers)));
+        }
+        SignalStream { state }
+    }
+}
+
+/// The values of a signal, created with [`Signal::to_stream`].
+pub struct SignalStream<T> {
+    state: Arc<Mutex<StreamState<T>>>,
+}
+
+struct StreamState<T> {
+    /// The newest value not yet yielded.
+    latest: Option<T>,
+    waker: Option<Waker>,
+    closed: bool,
+}
+
+/// The runtime's end of a [`SignalStream`]; dropping it ends the stream.
+struct StreamSender<T>(Arc<Mutex<StreamState<T>>>);
+
+impl<T: Clone + 'static> StreamSender<T> {
+    /// Replace the value waiting to be yielded with a copy of `value`, adding
+    /// the stream's waker to `wakers`. Returns false once the stream is gone.
+    fn send(&self, value: &dyn Any, wakers: &mut Vec<Waker>) -> bool {
+        if Arc::strong_count(&self.0) == 1 {
+            return false;
+        }
+        if let Some(value) = value.downcast_ref::<T>() {
+            let mut state = self.0.lock();
+            state.latest = Some(value.clone());
+            wakers.extend(state.waker.take());
+        }
+        true
+    }
+}
+
+impl<T> Drop for StreamSender<T> {
+    fn drop(&mut self) {
+        let waker = {
+            let mut state = self.0.lock();
+            state.closed = true;
+            state.waker.take()
+        };
+        if let Some(waker) = waker {
+            waker.wake();
+        }
+    }
+}
+
+impl<T> Stream for SignalStream<T> {
+    type Item = T;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<T>> {
+        let mut state = self.state.lock();
+        if let Some(value) = state.latest.take() {
+            return Poll::Ready(Some(value));
+        }
+        if state.closed || Arc::strong_count(&self.state) == 1 {
+            return Poll::Ready(None);
+        }
+        state.waker = Some(cx.waker().clone());
+        Poll::Pending
+    }
+}
+
+/// Create a signal that takes every item `stream` yields, starting at
+/// `initial`.
+///
+/// The stream is polled by a task handed to the spawner (see
+/// [`set_spawner`](super::set_spawner)); without one, the signal keeps its
+/// initial value. Like [`create_interval`](super::create_interval), the task
+/// is started on the first render only; the streams passed on re-renders are
+/// dropped. Once the scope is disposed, the task stops at the next item.
+///
+/// # Example
+/// ```ignore
+/// use momenta_core::signals::create_signal_from_stream;
+///
+/// let last_message = create_signal_from_stream(socket.messages(), None);
+/// ```
+pub fn create_signal_from_stream<T, S>(stream: S, initial: T) -> Signal<T>
+where
+    T: SignalValue + PartialEq + 'static,
+    S: Stream<Item = T> + 'static,
+{
+    let signal = create_signal(initial);
+    let started = create_signal(false);
+    if started.get_untracked() {
+        return signal;
+    }
+    started.set(true);
+    let mut stream = Box::pin(stream);
+    spawn(async move {
+        while let Some(item) = core::future::poll_fn(|cx| stream.as_mut().poll_next(cx)).await {
+            if signal.try_set(item).is_err() {
+                return;
+            }
+        }
+    });
+    signal
+}
+
+#[cfg(test)]
+mod tests {
+    extern crate std;
+
+    use super::*;
+    use crate::signals::{
+        LocalFuture, SPAWNER, TEST_MUTEX, batch, in_root_scope, reset_runtime_state, set_spawner,
+    };
+
+    #[test]
+    fn streams_yield_signal_changes_and_feed_signals() {
+        use core::cell::RefCell;
+
+        std::thread_local! {
+            static TASKS: RefCell<Vec<LocalFuture>> = const { RefCell::new(Vec::new()) };
+        }
+
+        let _guard = TEST_MUTEX.lock();
+        reset_runtime_state();
+        set_spawner(|future| TASKS.with(|tasks| tasks.borrow_mut().push(future)));
+
+        let ((count, mirror), scope) = in_root_scope(move || {
+            let count = create_signal(0);
+            let mirror = create_signal_from_stream(count.to_stream(), -1);
+            (count, mirror)
+        });
+        let mut changes = count.to_stream();
+        let mut task = TASKS.with(|tasks| tasks.borrow_mut().pop()).unwrap();
+        let mut cx = Context::from_waker(Waker::noop());
+        assert!(task.as_mut().poll(&mut cx).is_pending());
+
+        count.set(1);
+        batch(|| {
+            count.set(2);
+            count.set(3);
+        });
+        count.set(3);
+        let mut next = || {
+            let mut cx = Context::from_waker(Waker::noop());
+            Pin::new(&mut changes).poll_next(&mut cx)
+        };
+        // Changes made while the stream was not polled are coalesced.
+        assert_eq!(next(), Poll::Ready(Some(3)));
+        assert_eq!(next(), Poll::Pending);
+        count.set(4);
+        assert_eq!(next(), Poll::Ready(Some(4)));
+        assert_eq!(next(), Poll::Pending);
+
+        assert_eq!(mirror.get(), -1);
+        assert!(task.as_mut().poll(&mut cx).is_pending());
+        assert_eq!(mirror.get(), 4);
+
+        scope.dispose();
+        assert_eq!(next(), Poll::Ready(None));
+        assert!(task.as_mut().poll(&mut cx).is_ready());
+
+        *SPAWNER.lock() = None;
+    }
+
+    #[test]
+    fn streams_are_woken_without_the_runtime_lock() {
+        let _guard = TEST_MUTEX.lock();
+        reset_runtime_state();
+
+        /// Records whether the runtime was free to use when it was woken.
+        struct Probe(spin::Mutex<Vec<bool>>);
+        impl std::task::Wake for Probe {
+            fn wake(self: Arc<Self>) {
+                let unlocked = runtime().state.try_lock().is_some();
+                self.0.lock().push(unlocked);
+            }
+        }
+
+        let (count, scope) = in_root_scope(|| create_signal(0));
+        let mut changes = count.to_stream();
+        let probe = Arc::new(Probe(spin::Mutex::new(Vec::new())));
+        let waker = Waker::from(probe.clone());
+        let mut poll = || Pin::new(&mut changes).poll_next(&mut Context::from_waker(&waker));
+
+        assert_eq!(poll(), Poll::Pending);
+        count.set(1);
+        assert_eq!(poll(), Poll::Ready(Some(1)));
+        assert_eq!(poll(), Poll::Pending);
+        scope.dispose();
+        assert_eq!(poll(), Poll::Ready(None));
+        assert_eq!(*probe.0.lock(), [true, true]);
+    }
+}
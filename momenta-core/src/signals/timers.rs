@@ -0,0 +1,350 @@
+//! Timing helpers: [`debounced`], [`throttled`] and [`create_interval`],
+//! driven by the process-global [`Timer`].
+
+use super::{Runtime, ScopeHandle, Signal, SignalValue, create_signal, watch};
+use alloc::{boxed::Box, sync::Arc, vec::Vec};
+use spin::Mutex;
+
+type TimerCallback = Box<dyn FnOnce() + Send>;
+
+/// Identifies a timeout scheduled through a [`Timer`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub struct TimerId(pub u64);
+
+/// A source of time for [`debounced`], [`throttled`] and [`create_interval`].
+///
+/// `momenta_dom` installs a timer backed by the browser's `setTimeout` when it
+/// renders; tests can install a [`VirtualClock`] and move time by hand.
+pub trait Timer: Send + Sync {
+    /// Milliseconds elapsed since some fixed point in the past.
+    fn now(&self) -> u64;
+    /// Call `callback` once, `ms` milliseconds from now.
+    fn set_timeout(&self, ms: u32, callback: TimerCallback) -> TimerId;
+    /// Cancel a timeout that has not fired yet. Unknown ids are ignored.
+    fn clear_timeout(&self, id: TimerId);
+}
+
+pub(super) static TIMER: Mutex<Option<Arc<dyn Timer>>> = Mutex::new(None);
+
+/// Install the timer used by the timing helpers.
+///
+/// Without one, [`debounced`] and [`throttled`] pass every change straight
+/// through and [`create_interval`] never ticks, which is what server-side
+/// rendering wants.
+///
+/// The timer is process-global and shared by every [`Runtime`]; callbacks
+/// still run inside the runtime that scheduled them.
+///
+/// # Example
+/// ```ignore
+/// use momenta_core::signals::{VirtualClock, set_timer};
+///
+/// let clock = VirtualClock::new();
+/// set_timer(clock.clone());
+/// clock.advance(250); // fires every timeout due within the next 250ms
+/// ```
+pub fn set_timer(timer: impl Timer + 'static) {
+    *TIMER.lock() = Some(Arc::new(timer));
+}
+
+/// Returns true once [`set_timer`] has been called.
+pub fn has_timer() -> bool {
+    TIMER.lock().is_some()
+}
+
+pub(super) fn installed_timer() -> Option<Arc<dyn Timer>> {
+    TIMER.lock().clone()
+}
+
+/// Schedule `callback` inside the current runtime, skipping it if `scope` has
+/// been disposed by the time it fires.
+fn set_scoped_timeout(
+    scope: ScopeHandle,
+    ms: u32,
+    callback: impl FnOnce() + Send + 'static,
+) -> Option<TimerId> {
+    let timer = installed_timer()?;
+    let runtime = Runtime::current();
+    let callback = move || {
+        if scope.is_alive() {
+            callback();
+        }
+    };
+    Some(timer.set_timeout(
+        ms,
+        Box::new(move || match runtime {
+            Some(runtime) => runtime.enter(callback),
+            None => callback(),
+        }),
+    ))
+}
+
+/// A [`Timer`] whose clock only moves when told to, for deterministic tests.
+///
+/// Clones share the same clock.
+#[derive(Clone, Default)]
+pub struct VirtualClock {
+    state: Arc<Mutex<VirtualClockState>>,
+}
+
+#[derive(Default)]
+struct VirtualClockState {
+    now: u64,
+    next_id: u64,
+    pending: Vec<(u64, TimerId, TimerCallback)>,
+}
+
+impl VirtualClock {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Move the clock forward by `ms`, firing every timeout that falls due in
+    /// order, including ones scheduled by the callbacks themselves.
+    pub fn advance(&self, ms: u64) {
+        let target = self.state.lock().now + ms;
+        loop {
+            let next = {
+                let mut state = self.state.lock();
+                let due = state
+                    .pending
+                    .iter()
+                    .enumerate()
+                    .filter(|(_, (at, _, _))| *at <= target)
+                    .min_by_key(|(_, (at, id, _))| (*at, *id))
+                    .map(|(index, _)| index);
+                match due {
+                    Some(index) => {
+                        let (at, _, callback) = state.pending.remove(index);
+                        state.now = at;
+                        Some(callback)
+                    }
+                    None => {
+                        state.now = target;
+                        None
+                    }
+                }
+            };
+            match next {
+                // The lock is released so callbacks can schedule more timeouts.
+                Some(callback) => callback(),
+                None => break,
+            }
+        }
+    }
+
+    /// Number of timeouts waiting to fire.
+    pub fn pending(&self) -> usize {
+        self.state.lock().pending.len()
+    }
+}
+
+impl Timer for VirtualClock {
+    fn now(&self) -> u64 {
+        self.state.lock().now
+    }
+
+    fn set_timeout(&self, ms: u32, callback: TimerCallback) -> TimerId {
+        let mut state = self.state.lock();
+        state.next_id += 1;
+        let id = TimerId(state.next_id);
+        let at = state.now + u64::from(ms);
+        state.pending.push((at, id, callback));
+        id
+    }
+
+    fn clear_timeout(&self, id: TimerId) {
+        self.state
+            .lock()
+            .pending
+            .retain(|(_, pending, _)| *pending != id);
+    }
+}
+
+/// A signal that follows `source` once it has stopped changing for `ms`
+/// milliseconds.
+///
+/// Every change restarts the wait, so a burst of updates produces a single
+/// update of the returned signal with the last value.
+///
+/// # Example
+/// ```ignore
+/// use momenta_core::signals::{create_signal, debounced};
+///
+/// let query = create_signal(String::new());
+/// let search = debounced(query, 300);
+/// ```
+pub fn debounced<T>(source: Signal<T>, ms: u32) -> Signal<T>
+where
+    T: SignalValue + PartialEq + Clone + 'static,
+{
+    let output = create_signal(source.get_untracked());
+    let pending = create_signal(None::<TimerId>);
+    let Some(scope) = ScopeHandle::current() else {
+        return output;
+    };
+
+    watch(source, move |value, _| {
+        let Some(timer) = installed_timer() else {
+            output.set(value.clone());
+            return;
+        };
+        if let Some(id) = pending.get_untracked() {
+            timer.clear_timeout(id);
+        }
+        let value = value.clone();
+        let id = set_scoped_timeout(scope, ms, move || {
+            pending.set(None);
+            output.set(value);
+        });
+        pending.set(id);
+    });
+    output
+}
+
+/// A signal that follows `source` at most once every `ms` milliseconds.
+///
+/// The first change goes through immediately. Changes inside the window are
+/// held back and the latest one is applied when the window closes.
+///
+/// # Example
+/// ```ignore
+/// use momenta_core::signals::{create_signal, throttled};
+///
+/// let scroll = create_signal(0.0);
+/// let position = throttled(scroll, 100);
+/// ```
+pub fn throttled<T>(source: Signal<T>, ms: u32) -> Signal<T>
+where
+    T: SignalValue + PartialEq + Clone + 'static,
+{
+    let output = create_signal(source.get_untracked());
+    let last_emit = create_signal(None::<u64>);
+    let trailing = create_signal(false);
+    let Some(scope) = ScopeHandle::current() else {
+        return output;
+    };
+
+    watch(source, move |value, _| {
+        let Some(timer) = installed_timer() else {
+            output.set(value.clone());
+            return;
+        };
+        let now = timer.now();
+        let elapsed = last_emit
+            .get_untracked()
+            .map_or(u64::MAX, |last| now.saturating_sub(last));
+        if elapsed >= u64::from(ms) {
+            last_emit.set(Some(now));
+            output.set(value.clone());
+        } else if !trailing.get_untracked() {
+            trailing.set(true);
+            let wait = (u64::from(ms) - elapsed) as u32;
+            set_scoped_timeout(scope, wait, move || {
+                trailing.set(false);
+                last_emit.set(installed_timer().map(|timer| timer.now()));
+                output.set(source.get_untracked());
+            });
+        }
+    });
+    output
+}
+
+/// Call `f` every `ms` milliseconds for as long as the current scope lives.
+///
+/// The interval is started on the first render only; re-renders keep it
+/// running, and disposing the scope stops it.
+///
+/// # Example
+/// ```ignore
+/// use momenta_core::signals::{create_interval, create_signal};
+///
+/// let seconds = create_signal(0);
+/// create_interval(1000, move || seconds.set(seconds.get_untracked() + 1));
+/// ```
+pub fn create_interval(ms: u32, f: impl Fn() + Send + Sync + 'static) {
+    let started = create_signal(false);
+    if started.get_untracked() {
+        return;
+    }
+    started.set(true);
+    if let Some(scope) = ScopeHandle::current() {
+        schedule_interval(scope, ms, Arc::new(f));
+    }
+}
+
+fn schedule_interval(scope: ScopeHandle, ms: u32, f: Arc<dyn Fn() + Send + Sync>) {
+    set_scoped_timeout(scope, ms, move || {
+        f();
+        schedule_interval(scope, ms, f);
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::signals::{TEST_MUTEX, in_root_scope, reset_runtime_state};
+    use core::sync::atomic::{AtomicUsize, Ordering};
+
+    #[test]
+    fn debounced_and_throttled_follow_the_virtual_clock() {
+        let _guard = TEST_MUTEX.lock();
+        reset_runtime_state();
+        let clock = VirtualClock::new();
+        set_timer(clock.clone());
+
+        let renders = Arc::new(AtomicUsize::new(0));
+        let counter = renders.clone();
+        let ((source, debounce, throttle), _) = in_root_scope(move || {
+            counter.fetch_add(1, Ordering::SeqCst);
+            let source = create_signal(0);
+            let debounce = debounced(source, 100);
+            let throttle = throttled(source, 100);
+            let _ = debounce.get();
+            (source, debounce, throttle)
+        });
+
+        source.set(1);
+        assert_eq!((debounce.get(), throttle.get()), (0, 1));
+        clock.advance(50);
+        source.set(2);
+        source.set(3);
+        assert_eq!((debounce.get(), throttle.get()), (0, 1));
+        clock.advance(50);
+        assert_eq!((debounce.get(), throttle.get()), (0, 3));
+        clock.advance(49);
+        assert_eq!(debounce.get(), 0);
+        // Changes to the source alone do not re-render the scope.
+        assert_eq!(renders.load(Ordering::SeqCst), 1);
+        clock.advance(1);
+        assert_eq!(debounce.get(), 3);
+        assert_eq!(renders.load(Ordering::SeqCst), 2);
+
+        *TIMER.lock() = None;
+    }
+
+    #[test]
+    fn intervals_tick_until_their_scope_is_disposed() {
+        let _guard = TEST_MUTEX.lock();
+        reset_runtime_state();
+        let clock = VirtualClock::new();
+        set_timer(clock.clone());
+
+        let (ticks, scope) = in_root_scope(move || {
+            let ticks = create_signal(0);
+            create_interval(10, move || ticks.set(ticks.get_untracked() + 1));
+            let _ = ticks.get();
+            ticks
+        });
+
+        clock.advance(35);
+        assert_eq!(ticks.get(), 3);
+        assert_eq!(clock.pending(), 1);
+
+        scope.dispose();
+        clock.advance(100);
+        assert_eq!(clock.pending(), 0);
+
+        *TIMER.lock() = None;
+    }
+}
@@ -16,10 +16,10 @@ use alloc::{
     vec::Vec,
 };
 use momenta_core::nodes::{Component, Node};
-#[cfg(feature = "wasm")]
-use momenta_core::signals::run_scope;
 #[cfg(not(feature = "wasm"))]
 use momenta_core::signals::{has_current_scope, run_scope_transient};
+#[cfg(feature = "wasm")]
+use momenta_core::signals::{has_list_patcher, has_timer, run_scope, set_list_patcher, set_timer};
 
 #[cfg(feature = "wasm")]
 use wasm_bindgen::JsCast;
@@ -33,6 +33,8 @@ const HYDRATION_ID_ATTR: &str = "data-momenta-hid";
 const HYDRATION_ROOT_ATTR: &str = "data-momenta-root";
 #[cfg(feature = "wasm")]
 const DEFAULT_HYDRATION_STATE_ID: &str = "__MOMENTA_HYDRATION__";
+#[cfg(all(feature = "wasm", feature = "serde"))]
+const REACTIVE_STATE_ID: &str = "__MOMENTA_STATE__";
 
 #[cfg(feature = "wasm")]
 fn append_static_html(mount: &web_sys::Element, html: &str) {
@@ -80,15 +82,35 @@ mod element_cache {
     /// 3. There are no data races possible in a single-threaded context
     ///
     /// **Warning**: Do not use this code in multi-threaded environments.
-    struct ElementCache {
-        inner: UnsafeCell<Option<BTreeMap<String, web_sys::Element>>>,
+    struct ElementCache<T> {
+        inner: UnsafeCell<Option<BTreeMap<String, T>>>,
     }
 
     // SAFETY: This is only safe in WASM's single-threaded environment.
     // See struct documentation for details.
-    unsafe impl Sync for ElementCache {}
+    unsafe impl Sync for ElementCache<web_sys::Element> {}
+    // SAFETY: As above.
+    unsafe impl Sync for ElementCache<web_sys::Node> {}
+
+    impl<T> ElementCache<T> {
+        fn with<F, R>(&self, f: F) -> R
+        where
+            F: FnOnce(&mut BTreeMap<String, T>) -> R,
+        {
+            // SAFETY: Safe in WASM single-threaded environment. No concurrent access possible.
+            unsafe {
+                let cache = &mut *self.inner.get();
+                f(cache.get_or_insert_with(BTreeMap::new))
+            }
+        }
+    }
 
-    static ELEMENT_CACHE: ElementCache = ElementCache {
+    static ELEMENT_CACHE: ElementCache<web_sys::Element> = ElementCache {
+        inner: UnsafeCell::new(None),
+    };
+
+    /// Marker comments of keyed lists, by their text.
+    static LIST_MARKERS: ElementCache<web_sys::Node> = ElementCache {
         inner: UnsafeCell::new(None),
     };
 
@@ -102,13 +124,24 @@ mod element_cache {
     where
         F: FnOnce(&mut BTreeMap<String, web_sys::Element>) -> R,
     {
-        // SAFETY: Safe in WASM single-threaded environment. No concurrent access possible.
-        unsafe {
-            let cache = &mut *ELEMENT_CACHE.inner.get();
-            if cache.is_none() {
-                *cache = Some(BTreeMap::new());
-            }
-            f(cache.as_mut().unwrap())
+        ELEMENT_CACHE.with(f)
+    }
+
+    /// Access the list marker cache with a closure, see [`with_cache`].
+    pub(crate) fn with_markers<F, R>(f: F) -> R
+    where
+        F: FnOnce(&mut BTreeMap<String, web_sys::Node>) -> R,
+    {
+        LIST_MARKERS.with(f)
+    }
+
+    /// Remember `node` if it is the marker comment of a keyed list.
+    pub(crate) fn remember_marker(comment: &str, node: &web_sys::Node) {
+        if comment.starts_with(momenta_core::signals::LIST_MARKER_PREFIX) {
+            with_markers(|markers| {
+                use alloc::string::ToString;
+                markers.insert(comment.to_string(), node.clone());
+            });
         }
     }
 }
@@ -264,6 +297,7 @@ impl WasmRender for Node {
                 if let Some(document) = web_sys::window().and_then(|window| window.document()) {
                     let comment_node = document.create_comment(comment);
                     let _ = mount.append_child(&comment_node);
+                    element_cache::remember_marker(comment, &comment_node);
                 }
                 None
             }
@@ -382,11 +416,19 @@ where
 }
 
 #[cfg(feature = "wasm")]
+/// Hydrates server-rendered markup under the specified selector, falling back
+/// to a fresh render if it does not match. With the `serde` feature, values
+/// of serialized signals and resources sent by the server are restored first.
 pub fn hydrate_root<C: Component>(selectors: &'static str)
 where
     <C as Component>::Props: Default,
     <C as Component>::Props: Send + Sync + 'static,
 {
+    #[cfg(feature = "serde")]
+    if let Some(state) = read_hydration_data(REACTIVE_STATE_ID) {
+        momenta_core::signals::restore_hydration_state(&state).ok();
+    }
+
     render_component::<C>(Default::default(), move |node| {
         let window = web_sys::window().expect("no global `window` exists");
         let document = window.document().expect("should have a document on window");
@@ -416,6 +458,112 @@ pub fn read_default_hydration_data() -> Option<String> {
     read_hydration_data(DEFAULT_HYDRATION_STATE_ID)
 }
 
+/// A [`Timer`](momenta_core::signals::Timer) backed by the browser's
+/// `setTimeout`, installed automatically by [`render_root`] and
+/// [`hydrate_root`] unless another timer was set first.
+#[cfg(feature = "wasm")]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct BrowserTimer;
+
+#[cfg(feature = "wasm")]
+impl momenta_core::signals::Timer for BrowserTimer {
+    fn now(&self) -> u64 {
+        web_sys::window()
+            .and_then(|window| window.performance())
+            .map_or(0, |performance| performance.now() as u64)
+    }
+
+    fn set_timeout(
+        &self,
+        ms: u32,
+        callback: alloc::boxed::Box<dyn FnOnce() + Send>,
+    ) -> momenta_core::signals::TimerId {
+        let closure = wasm_bindgen::closure::Closure::once_into_js(callback);
+        let handle = web_sys::window()
+            .expect("no global `window` exists")
+            .set_timeout_with_callback_and_timeout_and_arguments_0(
+                closure.unchecked_ref(),
+                ms.min(i32::MAX as u32) as i32,
+            )
+            .expect("Failed to set timeout");
+        momenta_core::signals::TimerId(handle as u64)
+    }
+
+    fn clear_timeout(&self, id: momenta_core::signals::TimerId) {
+        if let Some(window) = web_sys::window() {
+            window.clear_timeout_with_handle(id.0 as i32);
+        }
+    }
+}
+
+/// A [`ListPatcher`](momenta_core::signals::ListPatcher) that moves, inserts
+/// and removes the elements of `map_keyed` lists in place, installed
+/// automatically by [`render_root`] and [`hydrate_root`] unless another
+/// patcher was set first.
+#[cfg(feature = "wasm")]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct DomListPatcher;
+
+#[cfg(feature = "wasm")]
+impl momenta_core::signals::ListPatcher for DomListPatcher {
+    fn patch_item(&self, node: &Node) {
+        let Node::Element(el) = node else {
+            return;
+        };
+        let Some(existing) = element_cache::with_cache(|cache| cache.get(el.key()).cloned()) else {
+            return;
+        };
+        let Some(parent) = existing.parent_element() else {
+            return;
+        };
+        // Patching may replace the element and append the new one at the end
+        // of the parent, so put it back where the old one was.
+        let next = existing.next_sibling();
+        if let Some(patched) = el.diff_and_patch(&existing, &parent)
+            && patched != existing
+        {
+            let _ = parent.insert_before(&patched, next.as_ref());
+        }
+    }
+
+    fn patch_list(&self, marker: &str, items: &[&Node], removed: &[String]) {
+        let Some(marker) = element_cache::with_markers(|markers| markers.get(marker).cloned())
+        else {
+            return;
+        };
+        let Some(parent) = marker.parent_element() else {
+            return;
+        };
+
+        for key in removed {
+            if let Some(element) = element_cache::with_cache(|cache| cache.remove(key)) {
+                element.remove();
+            }
+        }
+
+        // Walk backwards from the marker, moving each item in front of the
+        // one after it unless it is there already.
+        let mut next = marker;
+        for item in items.iter().rev() {
+            let Node::Element(el) = item else {
+                continue;
+            };
+            let cached = element_cache::with_cache(|cache| cache.get(el.key()).cloned());
+            let Some(element) = cached.or_else(|| el.render(&parent)) else {
+                continue;
+            };
+            if element.next_sibling().as_ref() != Some(&next) {
+                let _ = parent.insert_before(&element, Some(&next));
+            }
+            next = element.into();
+        }
+    }
+
+    fn dispose_list(&self, marker: &str) {
+        element_cache::with_markers(|markers| markers.remove(marker));
+    }
+}
+
 #[cfg(feature = "wasm")]
 /// Mounts the root component to the body element
 ///
@@ -542,8 +690,12 @@ fn hydrate_node(expected: &Node, existing: &web_sys::Node, path: &str, is_root:
                 && existing.text_content().as_deref() == Some(text.as_str())
         }
         Node::Comment(comment) => {
-            existing.node_type() == web_sys::Node::COMMENT_NODE
-                && existing.text_content().as_deref() == Some(comment.as_str())
+            let matches = existing.node_type() == web_sys::Node::COMMENT_NODE
+                && existing.text_content().as_deref() == Some(comment.as_str());
+            if matches {
+                element_cache::remember_marker(comment, existing);
+            }
+            matches
         }
         Node::Static(_) => false,
         Node::Fragment(_) | Node::Empty => false,
@@ -648,6 +800,12 @@ where
 {
     #[cfg(feature = "wasm")]
     {
+        if !has_timer() {
+            set_timer(BrowserTimer);
+        }
+        if !has_list_patcher() {
+            set_list_patcher(DomListPatcher);
+        }
         run_scope(
             move || C::render(&props),
             move |node| {
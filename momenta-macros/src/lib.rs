@@ -480,6 +480,35 @@ pub fn component(_attr: TokenStream, input: TokenStream) -> TokenStream {
     expanded.into()
 }
 
+/// Checks that a type can be stored in a signal.
+///
+/// Every `'static + Send` type implements `momenta::signals::SignalValue`
+/// already, so this derive generates no impl. It is kept so existing code
+/// keeps compiling, and it turns a missing `Send` into an error at the
+/// definition rather than at the first `create_signal`.
+///
+/// Generic types are checked with only the bounds they declare, so their
+/// parameters need `Send + 'static` bounds:
+///
+/// ```
+/// use momenta::prelude::*;
+///
+/// #[derive(SignalValue)]
+/// struct Labelled<T: Send + 'static> {
+///     label: String,
+///     value: T,
+/// }
+/// ```
+///
+/// ```compile_fail
+/// use momenta::prelude::*;
+///
+/// #[derive(SignalValue)]
+/// struct Labelled<T> {
+///     label: String,
+///     value: T,
+/// }
+/// ```
 #[proc_macro_derive(SignalValue)]
 pub fn derive_signal_value(input: TokenStream) -> TokenStream {
     let syn::DeriveInput {
@@ -488,15 +517,149 @@ pub fn derive_signal_value(input: TokenStream) -> TokenStream {
     let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
 
     let expanded = quote! {
-        impl #impl_generics ::momenta::signals::SignalValue for #ident #ty_generics #where_clause {
-            fn as_any(&self) -> Option<&dyn core::any::Any> {
-                Some(self)
+        const _: () = {
+            fn assert_signal_value<T: ::momenta::signals::SignalValue>() {}
+            #[allow(dead_code)]
+            fn assert_stored_type #impl_generics () #where_clause {
+                assert_signal_value::<#ident #ty_generics>();
             }
-        }
+        };
     };
     expanded.into()
 }
 
+/// Derives `momenta::signals::Store` for a struct with named fields.
+///
+/// Generates a `<Name>Store` type, returned by `create_store`, with one method
+/// per field that returns a `Lens` onto it. Mark fields whose type also derives
+/// `Store` with `#[store(nested)]` to get their accessors directly, as in
+/// `state.user().name().set(..)`.
+///
+/// # Examples
+///
+/// ```rust ignore
+/// use momenta::prelude::*;
+///
+/// #[derive(Store)]
+/// struct Todo {
+///     title: String,
+///     done: bool,
+/// }
+///
+/// #[derive(Store)]
+/// struct AppState {
+///     filter: String,
+///     todos: Vec<Todo>,
+/// }
+///
+/// let state = create_store(AppState { filter: String::new(), todos: vec![] });
+/// state.todos().at(0).fields().done().set(true);
+/// ```
+#[proc_macro_derive(Store, attributes(store))]
+pub fn derive_store(input: TokenStream) -> TokenStream {
+    let input = syn::parse_macro_input!(input as syn::DeriveInput);
+    match expand_store(input) {
+        Ok(tokens) => tokens.into(),
+        Err(err) => err.to_compile_error().into(),
+    }
+}
+
+fn expand_store(input: syn::DeriveInput) -> Result<TokenStream2> {
+    let syn::DeriveInput {
+        vis,
+        ident,
+        generics,
+        data,
+        ..
+    } = input;
+    let fields = match data {
+        syn::Data::Struct(syn::DataStruct {
+            fields: syn::Fields::Named(fields),
+            ..
+        }) => fields.named,
+        _ => {
+            return Err(syn::Error::new(
+                ident.span(),
+                "Store can only be derived for structs with named fields",
+            ));
+        }
+    };
+
+    let store_ident = Ident::new(&format!("{}Store", ident), ident.span());
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+    let params = &generics.params;
+
+    let mut accessors = Vec::new();
+    for (index, field) in fields.iter().enumerate() {
+        let mut nested = false;
+        for attr in field.attrs.iter().filter(|a| a.path().is_ident("store")) {
+            attr.parse_nested_meta(|meta| {
+                if meta.path.is_ident("nested") {
+                    nested = true;
+                    Ok(())
+                } else {
+                    Err(meta.error("expected `nested`"))
+                }
+            })?;
+        }
+
+        let field_vis = &field.vis;
+        let name = field.ident.as_ref().unwrap();
+        let ty = &field.ty;
+        let index = index as u64;
+        let lens = quote! {
+            self.lens.field::<#ty>(#index, |value| &value.#name, |value| &mut value.#name)
+        };
+        accessors.push(if nested {
+            quote! {
+                #field_vis fn #name(&self) -> <#ty as ::momenta::signals::Store>::Fields {
+                    <#ty as ::momenta::signals::Store>::fields(#lens)
+                }
+            }
+        } else {
+            quote! {
+                #field_vis fn #name(&self) -> ::momenta::signals::Lens<#ty> {
+                    #lens
+                }
+            }
+        });
+    }
+
+    let doc = format!("Field accessors for [`{}`].", ident);
+    Ok(quote! {
+        #[doc = #doc]
+        #vis struct #store_ident <#params> #where_clause {
+            lens: ::momenta::signals::Lens<#ident #ty_generics>,
+        }
+
+        impl #impl_generics ::core::clone::Clone for #store_ident #ty_generics #where_clause {
+            fn clone(&self) -> Self {
+                Self { lens: self.lens.clone() }
+            }
+        }
+
+        impl #impl_generics ::core::ops::Deref for #store_ident #ty_generics #where_clause {
+            type Target = ::momenta::signals::Lens<#ident #ty_generics>;
+
+            fn deref(&self) -> &Self::Target {
+                &self.lens
+            }
+        }
+
+        impl #impl_generics #store_ident #ty_generics #where_clause {
+            #(#accessors)*
+        }
+
+        impl #impl_generics ::momenta::signals::Store for #ident #ty_generics #where_clause {
+            type Fields = #store_ident #ty_generics;
+
+            fn fields(lens: ::momenta::signals::Lens<Self>) -> Self::Fields {
+                #store_ident { lens }
+            }
+        }
+    })
+}
+
 /// A procedural macro that provides JSX-like syntax for creating HTML elements in Rust.
 ///
 /// # Examples
@@ -415,6 +415,12 @@ impl RouterContext {
         self.current_path
     }
 
+    /// Make this router available to descendant components through [`use_router`].
+    pub fn provide(self) -> Self {
+        provide_context(self);
+        self
+    }
+
     pub fn href(&self, path: &str) -> String {
         let normalized_path = Self::normalize_path(path);
 
@@ -425,6 +431,11 @@ impl RouterContext {
     }
 }
 
+/// Returns the nearest [`RouterContext`] published with [`RouterContext::provide`].
+pub fn use_router() -> Option<RouterContext> {
+    use_context::<RouterContext>()
+}
+
 #[cfg(test)]
 mod tests {
     use super::RouterContext;
@@ -470,6 +481,29 @@ mod tests {
         );
     }
 
+    #[test]
+    fn provided_router_is_visible_to_nested_scopes() {
+        run_scope_transient(
+            || {
+                let router = RouterContext::with_path(RouterMode::Hash, "/docs").provide();
+
+                run_scope_transient(
+                    move || {
+                        let nested = super::use_router().expect("router was provided");
+                        assert_eq!(nested.current_path().get(), "/docs");
+
+                        router.navigate("/guide");
+                        assert_eq!(nested.current_path().get(), "/guide");
+
+                        Node::Empty
+                    },
+                    |_| {},
+                )
+            },
+            |_| {},
+        );
+    }
+
     #[test]
     fn base_path_is_stripped_from_initial_paths() {
         assert_eq!(RouterContext::strip_base_path("/momenta", "/momenta"), "/");
@@ -5,17 +5,24 @@
 //! - Chunked HTML rendering
 //! - Hydratable HTML output with stable element markers
 //! - Embedded JSON state blobs for client-side resume
+//! - Async rendering that waits for resources before emitting HTML
 //! - Thin adapters for Axum, Actix, and Hyper
+//!
+//! Every render runs inside its own reactive [`Runtime`], so concurrent
+//! requests never share signals and never wait on each other.
 
 use core::fmt::{self, Write};
 use momenta::{
     nodes::{Component, Element, Node},
-    signals::run_scope_transient,
+    signals::{Runtime, ScopeHandle, TaskSet, run_scope, run_scope_transient},
 };
+use std::sync::{Arc, Mutex};
 
 pub const HYDRATION_ID_ATTR: &str = "data-momenta-hid";
 pub const HYDRATION_ROOT_ATTR: &str = "data-momenta-root";
 pub const DEFAULT_HYDRATION_STATE_ID: &str = "__MOMENTA_HYDRATION__";
+/// Script id holding the values of serialized resources, read by `hydrate_root`.
+pub const REACTIVE_STATE_ID: &str = "__MOMENTA_STATE__";
 
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub struct RenderOptions {
@@ -84,18 +91,49 @@ pub fn render_to_hydration_string(
     render: impl FnOnce() -> Node + Send + 'static,
     options: HydrationOptions,
 ) -> String {
-    let node = render_node(render);
-    let mut collector = ChunkCollector::new(options.render.chunk_size);
-    write_hydratable_root(&node, &mut collector);
-
-    if let Some(state_json) = options.state_json.as_deref() {
-        collector.push_str(&render_hydration_state_script(
-            &options.state_script_id,
-            state_json,
-        ));
-    }
+    let mut render = Some(render);
+    let (node, reactive_state) = Runtime::new().enter(move || {
+        let node = run_scope_transient(
+            move || render.take().expect("render closure should only run once")(),
+            |_| {},
+        );
+        (node, take_reactive_state())
+    });
+    write_hydration_document(&node, reactive_state.as_deref(), &options)
+}
+
+/// Render to HTML once every resource created during the render has settled.
+///
+/// The tree is rendered, the resources it creates are awaited, and the tree is
+/// re-rendered as they resolve until no more work is pending, so the output
+/// contains resolved data instead of loading states. `render` may therefore
+/// run more than once.
+///
+/// The returned future is not `Send`; on multi-threaded runtimes await it
+/// inside a `tokio::task::LocalSet` or with a local executor.
+///
+/// # Example
+/// ```ignore
+/// use momenta_ssr::render_to_string_async;
+///
+/// let html = render_to_string_async(|| rsx!(<UserProfile />)).await;
+/// ```
+pub async fn render_to_string_async(render: impl FnMut() -> Node + Send + 'static) -> String {
+    let (node, _) = render_node_async(render).await;
+    node.to_html()
+}
 
-    collector.finish().concat()
+/// Like [`render_to_hydration_string`], but waits for resources first (see
+/// [`render_to_string_async`]). With the `serde` feature, values of serialized
+/// resources are written to the [`REACTIVE_STATE_ID`] script, so the client
+/// starts out with them instead of fetching again. Resources created with
+/// plain `create_resource` are not recorded and fetch again on the client.
+pub async fn render_to_hydration_string_async(
+    render: impl FnMut() -> Node + Send + 'static,
+    options: HydrationOptions,
+) -> String {
+    let (node, reactive_state) = render_node_async(render).await;
+    write_hydration_document(&node, reactive_state.as_deref(), &options)
 }
 
 pub fn render_hydration_state_script(script_id: &str, state_json: &str) -> String {
@@ -238,13 +276,89 @@ type HyperChunkStream = futures_util::stream::Iter<
     std::vec::IntoIter<Result<hyper::body::Frame<bytes::Bytes>, core::convert::Infallible>>,
 >;
 
+fn write_hydration_document(
+    node: &Node,
+    reactive_state: Option<&str>,
+    options: &HydrationOptions,
+) -> String {
+    let mut collector = ChunkCollector::new(options.render.chunk_size);
+    write_hydratable_root(node, &mut collector);
+
+    if let Some(state_json) = options.state_json.as_deref() {
+        collector.push_str(&render_hydration_state_script(
+            &options.state_script_id,
+            state_json,
+        ));
+    }
+
+    if let Some(reactive_state) = reactive_state {
+        collector.push_str(&render_hydration_state_script(
+            REACTIVE_STATE_ID,
+            reactive_state,
+        ));
+    }
+
+    collector.finish().concat()
+}
+
+/// Render inside a fresh runtime, keeping the root scope alive until every
+/// task spawned by the tree has completed, then dispose it so effect cleanups
+/// run. Returns the latest render and the serialized reactive state.
+async fn render_node_async(
+    mut render: impl FnMut() -> Node + Send + 'static,
+) -> (Node, Option<String>) {
+    let runtime = Runtime::new();
+    let latest = Arc::new(Mutex::new(Node::Empty));
+    let root = Arc::new(Mutex::new(None));
+
+    let (captured, captured_root) = (latest.clone(), root.clone());
+    let (_, tasks) = TaskSet::collect(|| {
+        runtime.enter(move || {
+            run_scope(
+                move || {
+                    captured_root
+                        .lock()
+                        .unwrap()
+                        .get_or_insert_with(ScopeHandle::current);
+                    render()
+                },
+                move |node| {
+                    *captured.lock().unwrap() = node.clone();
+                },
+            )
+        })
+    });
+    tasks.await;
+
+    let node = core::mem::replace(&mut *latest.lock().unwrap(), Node::Empty);
+    let reactive_state = runtime.enter(take_reactive_state);
+    if let Some(root) = root.lock().unwrap().take().flatten() {
+        runtime.enter(|| root.dispose());
+    }
+    (node, reactive_state)
+}
+
+/// The values recorded by serialized signals and resources in the current
+/// runtime. Without the `serde` feature nothing can be recorded.
+#[cfg(feature = "serde")]
+fn take_reactive_state() -> Option<String> {
+    momenta::signals::take_hydration_state()
+}
+
+#[cfg(not(feature = "serde"))]
+fn take_reactive_state() -> Option<String> {
+    None
+}
+
 fn render_node(render: impl FnOnce() -> Node + Send + 'static) -> Node {
     let mut render = Some(render);
 
-    run_scope_transient(
-        move || render.take().expect("render closure should only run once")(),
-        |_| {},
-    )
+    Runtime::new().enter(move || {
+        run_scope_transient(
+            move || render.take().expect("render closure should only run once")(),
+            |_| {},
+        )
+    })
 }
 
 fn write_node_to_writer(
@@ -514,6 +628,31 @@ mod tests {
         assert_eq!(html, "<div>3</div>");
     }
 
+    #[test]
+    fn concurrent_renders_do_not_block_each_other() {
+        use std::sync::{Arc, Barrier};
+
+        // Both renders wait for each other while inside their render closure,
+        // which deadlocks if renders are serialized.
+        let barrier = Arc::new(Barrier::new(2));
+        let handles: Vec<_> = (0..2)
+            .map(|i| {
+                let barrier = barrier.clone();
+                std::thread::spawn(move || {
+                    render_to_string(move || {
+                        let count = create_signal(i);
+                        barrier.wait();
+                        count.set(count.get() * 10);
+                        element("div", vec![Node::from(count.get())])
+                    })
+                })
+            })
+            .collect();
+
+        let html: Vec<_> = handles.into_iter().map(|h| h.join().unwrap()).collect();
+        assert_eq!(html, vec!["<div>0</div>", "<div>10</div>"]);
+    }
+
     #[test]
     fn render_to_chunks_splits_output() {
         let chunks = render_to_chunks(
@@ -575,6 +714,82 @@ mod tests {
         ));
     }
 
+    #[cfg(feature = "serde")]
+    #[test]
+    fn render_to_hydration_string_collects_serialized_signals() {
+        use momenta::signals::create_serialized_signal;
+
+        let html = render_to_hydration_string(
+            || {
+                let theme = create_serialized_signal("theme", String::from("light"));
+                theme.set(String::from("dark"));
+                element("p", vec![Node::from(theme.get())])
+            },
+            HydrationOptions::default(),
+        );
+
+        assert!(html.ends_with(
+            "<script id=\"__MOMENTA_STATE__\" type=\"application/json\">{\"theme\":\"dark\"}</script>"
+        ));
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn async_render_waits_for_resources() {
+        use momenta::signals::create_serialized_resource;
+
+        let html = futures_executor::block_on(render_to_hydration_string_async(
+            || {
+                let user = create_serialized_resource("user", || async { String::from("Ada") });
+                let name = user.get().unwrap_or_else(|| String::from("loading"));
+                element("p", vec![Node::from(name)])
+            },
+            HydrationOptions::default(),
+        ));
+
+        assert!(html.contains(">Ada</p>"));
+        assert!(html.ends_with(
+            "<script id=\"__MOMENTA_STATE__\" type=\"application/json\">{\"user\":\"Ada\"}</script>"
+        ));
+    }
+
+    #[test]
+    fn async_render_does_not_record_plain_resources() {
+        use momenta::signals::create_resource;
+
+        let html = futures_executor::block_on(render_to_hydration_string_async(
+            || {
+                let user = create_resource(|| async { String::from("Ada") });
+                let name = user.get().unwrap_or_else(|| String::from("loading"));
+                element("p", vec![Node::from(name)])
+            },
+            HydrationOptions::default(),
+        ));
+
+        assert!(html.contains(">Ada</p>"));
+        assert!(!html.contains(REACTIVE_STATE_ID));
+    }
+
+    #[test]
+    fn async_render_runs_effect_cleanups() {
+        use momenta::signals::create_effect_with_cleanup;
+        use std::sync::atomic::{AtomicBool, Ordering};
+
+        let cleaned_up = Arc::new(AtomicBool::new(false));
+        let captured = cleaned_up.clone();
+        let html = futures_executor::block_on(render_to_string_async(move || {
+            let cleaned_up = captured.clone();
+            create_effect_with_cleanup(move || {
+                let cleaned_up = cleaned_up.clone();
+                move || cleaned_up.store(true, Ordering::SeqCst)
+            });
+            element("p", vec![Node::from("done")])
+        }));
+
+        assert_eq!(html, "<p>done</p>");
+        assert!(cleaned_up.load(Ordering::SeqCst));
+    }
+
     #[test]
     fn hydration_state_script_escapes_script_breakouts() {
         let script = render_hydration_state_script("state", "</script><div>");
@@ -168,6 +168,9 @@ extern crate alloc;
 // Re-export core types
 pub use momenta_core::{nodes, signals};
 
+#[cfg(any(feature = "futures-executor", feature = "tokio"))]
+pub use momenta_core::executor;
+
 // Re-export DOM functionality when available
 #[cfg(feature = "dom")]
 pub use momenta_dom as dom;
@@ -179,14 +182,16 @@ pub mod prelude {
     #[cfg(any(feature = "computed", feature = "full-reactivity"))]
     pub use momenta_core::signals::create_computed;
     #[cfg(any(feature = "memoization", feature = "full-reactivity"))]
-    pub use momenta_core::signals::create_memo;
+    pub use momenta_core::signals::{create_memo, create_memo_cache};
     pub use momenta_core::signals::{
-        Signal, SignalValue, batch, create_effect, create_effect_with_cleanup, create_signal,
+        Lens, Signal, SignalValue, Store, batch, create_async_effect, create_effect,
+        create_effect_with_cleanup, create_layout_effect, create_render_effect, create_signal,
+        create_store, map_keyed, provide_context, transaction, untrack, use_context, watch,
     };
     #[cfg(feature = "wasm")]
     pub use momenta_dom::{
         component, hydrate_root, mount_to_body, read_default_hydration_data, read_hydration_data,
         render_root,
     };
-    pub use momenta_macros::{SignalValue, component, rsx, when};
+    pub use momenta_macros::{SignalValue, Store, component, rsx, when};
 }
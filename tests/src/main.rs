@@ -394,4 +394,48 @@ mod tests {
             "<ul><li>Item: X</li><li>Item: Y</li><li>Item: Z</li></ul>"
         )
     }
+
+    #[derive(Store)]
+    struct Profile {
+        name: String,
+        age: u32,
+    }
+
+    #[derive(Store)]
+    struct AppState {
+        #[store(nested)]
+        profile: Profile,
+        tags: Vec<Profile>,
+    }
+
+    #[test]
+    fn test_store_derive_field_lenses() {
+        momenta::signals::Runtime::new().enter(|| {
+            let html = momenta::signals::run_scope_transient(
+                || {
+                    let state = create_store(AppState {
+                        profile: Profile {
+                            name: "Ada".to_string(),
+                            age: 36,
+                        },
+                        tags: vec![],
+                    });
+                    state.profile().name().set("Grace".to_string());
+                    state.tags().push(Profile {
+                        name: "admin".to_string(),
+                        age: 0,
+                    });
+                    let summary = format!(
+                        "{} {} {}",
+                        state.profile().name().get(),
+                        state.profile().age().get(),
+                        state.tags().at(0).fields().name().get()
+                    );
+                    rsx!(<p>{summary}</p>)
+                },
+                |_| {},
+            );
+            assert_eq!(html.to_string(), "<p>Grace 36 admin</p>");
+        });
+    }
 }